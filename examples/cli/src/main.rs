@@ -0,0 +1,150 @@
+use clap::{Parser, Subcommand};
+use std::io;
+use std::time::{Duration, Instant};
+use zeroconf::json::{
+    json_lines_browser_callback, json_lines_registered_callback, write_json_line,
+};
+use zeroconf::prelude::*;
+use zeroconf::{MdnsBrowser, MdnsResolver, MdnsService, ServiceType, TxtRecord};
+
+/// Command line tool to browse, register and resolve mDNS services
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Browse for services of a type, printing each event as a line of JSON
+    Browse {
+        /// Service type to browse, e.g. `_http._tcp`
+        service_type: ServiceType,
+
+        /// Stop browsing after this many seconds
+        #[clap(short, long)]
+        timeout: Option<u64>,
+    },
+    /// Register a service, printing the registration as JSON
+    Register {
+        /// Service type to register, e.g. `_http._tcp`
+        service_type: ServiceType,
+
+        /// Port to advertise the service on
+        port: u16,
+
+        /// Name to register the service under
+        #[clap(short, long)]
+        name: Option<String>,
+
+        /// TXT record entries in the form `key=value`
+        #[clap(long, value_parser = parse_txt_entry)]
+        txt: Vec<(String, String)>,
+    },
+    /// Resolve a service instance by name, printing it as JSON
+    Resolve {
+        /// Service type of the instance, e.g. `_http._tcp`
+        service_type: ServiceType,
+
+        /// Name of the service instance to resolve
+        name: String,
+
+        /// Give up after this many seconds
+        #[clap(short, long, default_value_t = 10)]
+        timeout: u64,
+    },
+}
+
+fn main() -> zeroconf::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::new().filter_or("RUST_LOG", "warn")).init();
+
+    match Args::parse().command {
+        Command::Browse {
+            service_type,
+            timeout,
+        } => browse(service_type, timeout.map(Duration::from_secs)),
+        Command::Register {
+            service_type,
+            port,
+            name,
+            txt,
+        } => register(service_type, port, name, txt),
+        Command::Resolve {
+            service_type,
+            name,
+            timeout,
+        } => resolve(service_type, name, Duration::from_secs(timeout)),
+    }
+}
+
+fn browse(service_type: ServiceType, timeout: Option<Duration>) -> zeroconf::Result<()> {
+    let mut browser = MdnsBrowser::new(service_type);
+
+    browser.set_service_callback(json_lines_browser_callback(io::stdout()));
+
+    let event_loop = browser.browse_services()?;
+    let start = Instant::now();
+
+    loop {
+        event_loop.poll(Duration::from_millis(100))?;
+
+        if matches!(timeout, Some(timeout) if start.elapsed() >= timeout) {
+            return Ok(());
+        }
+    }
+}
+
+fn register(
+    service_type: ServiceType,
+    port: u16,
+    name: Option<String>,
+    txt: Vec<(String, String)>,
+) -> zeroconf::Result<()> {
+    let mut service = MdnsService::new(service_type, port);
+
+    if let Some(name) = name {
+        service.set_name(&name);
+    }
+
+    if !txt.is_empty() {
+        let mut txt_record = TxtRecord::new();
+
+        for (key, value) in txt {
+            txt_record.insert(&key, &value)?;
+        }
+
+        service.set_txt_record(txt_record);
+    }
+
+    service.set_registered_callback(json_lines_registered_callback(io::stdout()));
+
+    let event_loop = service.register()?;
+
+    loop {
+        // calling `poll()` will keep this service alive
+        event_loop.poll(Duration::from_secs(1))?;
+    }
+}
+
+fn resolve(service_type: ServiceType, name: String, timeout: Duration) -> zeroconf::Result<()> {
+    let mut resolver = MdnsResolver::new(service_type, &name);
+    let results = resolver.resolved_channel();
+    let event_loop = resolver.resolve()?;
+
+    event_loop.poll_until(Instant::now() + timeout)?;
+
+    let service = results
+        .try_recv()
+        .map_err(|_| zeroconf::Error::BrowserError("timed out resolving service".into()))??;
+
+    write_json_line(&mut io::stdout(), &service)
+        .map_err(|e| zeroconf::Error::BrowserError(e.to_string()))
+}
+
+fn parse_txt_entry(entry: &str) -> Result<(String, String), String> {
+    entry
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected `key=value`, got `{}`", entry))
+}
@@ -2,13 +2,13 @@
 
 use crate::{ffi::c_str, Error};
 use avahi_sys::{
-    avahi_address_snprint, avahi_alternative_service_name, avahi_strerror, AvahiAddress,
-    AvahiClient,
+    avahi_address_snprint, avahi_alternative_service_name, avahi_service_name_join, avahi_strerror,
+    AvahiAddress, AvahiClient, AvahiLookupResultFlags, AvahiProtocol, AVAHI_DOMAIN_NAME_MAX,
 };
 use libc::c_char;
 use std::ffi::CStr;
 
-use crate::{NetworkInterface, Result, ServiceType};
+use crate::{IpProtocol, NetworkInterface, Result, ServiceType};
 
 /// Converts the specified `*const AvahiAddress` to a `String`.
 ///
@@ -75,6 +75,26 @@ pub fn interface_from_index(index: i32) -> NetworkInterface {
     }
 }
 
+/// Converts the specified Avahi protocol to an [`IpProtocol`].
+///
+/// [`IpProtocol`]: ../../enum.IpProtocol.html
+pub fn protocol_from_avahi(protocol: AvahiProtocol) -> IpProtocol {
+    match protocol {
+        avahi_sys::AVAHI_PROTO_INET => IpProtocol::V4,
+        avahi_sys::AVAHI_PROTO_INET6 => IpProtocol::V6,
+        _ => IpProtocol::Unspec,
+    }
+}
+
+/// Returns true if the specified `AvahiLookupResultFlags` indicate that the result originates
+/// from the local host.
+pub fn is_local_result(flags: AvahiLookupResultFlags) -> bool {
+    flags
+        & (avahi_sys::AvahiLookupResultFlags_AVAHI_LOOKUP_RESULT_OUR_OWN
+            | avahi_sys::AvahiLookupResultFlags_AVAHI_LOOKUP_RESULT_LOCAL)
+        != 0
+}
+
 /// Executes the specified closure and returns a formatted `Result`
 ///
 /// # Safety
@@ -131,6 +151,29 @@ pub unsafe fn alternative_service_name(name: &CStr) -> &CStr {
     CStr::from_ptr(avahi_alternative_service_name(name.as_ptr()))
 }
 
+/// Returns the full DNS name of the service instance with the specified name, type and domain.
+///
+/// # Safety
+/// This function is unsafe because of the call to `avahi_service_name_join`.
+pub unsafe fn join_service_name(name: &CStr, kind: &CStr, domain: &CStr) -> Result<String> {
+    let mut full_name = vec![0 as c_char; AVAHI_DOMAIN_NAME_MAX as usize];
+
+    sys_exec(
+        || {
+            avahi_service_name_join(
+                full_name.as_mut_ptr(),
+                full_name.len(),
+                name.as_ptr(),
+                kind.as_ptr(),
+                domain.as_ptr(),
+            )
+        },
+        "could not join service name",
+    )?;
+
+    Ok(c_str::raw_to_str(full_name.as_ptr()).to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +224,31 @@ mod tests {
         assert_eq!(interface_from_index(1), NetworkInterface::AtIndex(1));
     }
 
+    #[test]
+    fn protocol_from_avahi_returns_protocol() {
+        assert_eq!(protocol_from_avahi(AVAHI_PROTO_INET), IpProtocol::V4);
+        assert_eq!(protocol_from_avahi(AVAHI_PROTO_INET6), IpProtocol::V6);
+        assert_eq!(
+            protocol_from_avahi(avahi_sys::AVAHI_PROTO_UNSPEC),
+            IpProtocol::Unspec
+        );
+    }
+
+    #[test]
+    fn is_local_result_returns_true_for_our_own() {
+        assert!(is_local_result(
+            avahi_sys::AvahiLookupResultFlags_AVAHI_LOOKUP_RESULT_OUR_OWN
+                | avahi_sys::AvahiLookupResultFlags_AVAHI_LOOKUP_RESULT_MULTICAST
+        ));
+    }
+
+    #[test]
+    fn is_local_result_returns_false_for_remote() {
+        assert!(!is_local_result(
+            avahi_sys::AvahiLookupResultFlags_AVAHI_LOOKUP_RESULT_MULTICAST
+        ));
+    }
+
     #[test]
     fn format_service_type_returns_valid_string() {
         assert_eq!(
@@ -220,6 +288,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn join_service_name_returns_full_name() {
+        let full_name = unsafe {
+            join_service_name(
+                &c_string!("foo"),
+                &c_string!("_http._tcp"),
+                &c_string!("local"),
+            )
+        };
+
+        assert_eq!(full_name.unwrap(), "foo._http._tcp.local");
+    }
+
     #[test]
     fn get_error_returns_valid_error_string() {
         assert_eq!(
@@ -4,30 +4,34 @@ use super::avahi_util;
 use super::client::{ManagedAvahiClient, ManagedAvahiClientParams};
 use super::poll::ManagedAvahiSimplePoll;
 use super::raw_browser::{ManagedAvahiServiceBrowser, ManagedAvahiServiceBrowserParams};
+use super::timeout::{ManagedAvahiTimeout, ManagedAvahiTimeoutParams};
 use super::{
     resolver::{
         ManagedAvahiServiceResolver, ManagedAvahiServiceResolverParams, ServiceResolverSet,
     },
     string_list::ManagedAvahiStringList,
 };
-use crate::ffi::{c_str, AsRaw, FromRaw};
+use crate::browser::DuplicateNameDetector;
+use crate::event_loop::{Dispatcher, Termination};
+use crate::ffi::{c_str, AsRaw, FromRaw, UnwrapOrNull};
 use crate::prelude::*;
 use crate::{Error, Result};
 use crate::{
-    BrowserEvent, EventLoop, NetworkInterface, ServiceBrowserCallback, ServiceDiscovery,
-    ServiceRemoval, ServiceType, TxtRecord,
+    BrowserEvent, EventLoop, NetworkInterface, ServiceAddress, ServiceBrowserCallback,
+    ServiceDiscovery, ServiceInstance, ServiceRemoval, ServiceType, TxtRecord,
 };
 use avahi_sys::{
     AvahiAddress, AvahiBrowserEvent, AvahiClient, AvahiClientFlags, AvahiClientState, AvahiIfIndex,
     AvahiLookupResultFlags, AvahiProtocol, AvahiResolverEvent, AvahiServiceBrowser,
-    AvahiServiceResolver, AvahiStringList,
+    AvahiServiceResolver, AvahiStringList, AvahiTimeout,
 };
 use libc::{c_char, c_void};
 use std::any::Any;
 use std::ffi::CString;
+use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::{fmt, ptr};
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct AvahiMdnsBrowser {
@@ -56,6 +60,30 @@ impl TMdnsBrowser for AvahiMdnsBrowser {
         avahi_util::interface_from_index(self.context.interface_index)
     }
 
+    fn set_domain(&mut self, domain: &str) {
+        self.context.domain = Some(c_string!(domain));
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.context.domain.as_ref().map(c_str::to_str)
+    }
+
+    fn set_auto_resolve(&mut self, auto_resolve: bool) {
+        self.context.auto_resolve = auto_resolve;
+    }
+
+    fn auto_resolve(&self) -> bool {
+        self.context.auto_resolve
+    }
+
+    fn set_resolve_timeout(&mut self, timeout: Option<Duration>) {
+        self.context.resolve_timeout = timeout;
+    }
+
+    fn resolve_timeout(&self) -> Option<Duration> {
+        self.context.resolve_timeout
+    }
+
     fn set_service_callback(&mut self, service_callback: Box<ServiceBrowserCallback>) {
         self.context.service_callback = Some(service_callback);
     }
@@ -94,6 +122,7 @@ impl TMdnsBrowser for AvahiMdnsBrowser {
         self.client = Some(Arc::new(unsafe { ManagedAvahiClient::new(client_params) }?));
 
         self.context.client.clone_from(&self.client);
+        self.context.poll.clone_from(&self.poll);
 
         unsafe {
             if let Err(e) = create_browser(&mut self.context) {
@@ -106,6 +135,8 @@ impl TMdnsBrowser for AvahiMdnsBrowser {
                 .as_ref()
                 .ok_or(Error::BrowserError("could not get poll as ref".into()))?
                 .clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
         ))
     }
 }
@@ -113,34 +144,71 @@ impl TMdnsBrowser for AvahiMdnsBrowser {
 #[derive(FromRaw, AsRaw)]
 struct AvahiBrowserContext {
     client: Option<Arc<ManagedAvahiClient>>,
+    poll: Option<Arc<ManagedAvahiSimplePoll>>,
     resolvers: ServiceResolverSet,
     service_callback: Option<Box<ServiceBrowserCallback>>,
     user_context: Option<Arc<dyn Any>>,
     interface_index: AvahiIfIndex,
+    auto_resolve: bool,
+    resolve_timeout: Option<Duration>,
     kind: CString,
+    domain: Option<CString>,
     browser: Option<ManagedAvahiServiceBrowser>,
+    duplicates: DuplicateNameDetector,
+    termination: Termination,
+    dispatcher: Dispatcher,
 }
 
 impl AvahiBrowserContext {
     fn new(kind: CString, interface_index: AvahiIfIndex) -> Self {
         Self {
             client: None,
+            poll: None,
             resolvers: ServiceResolverSet::default(),
             service_callback: None,
             user_context: None,
             interface_index,
+            auto_resolve: true,
+            resolve_timeout: None,
             kind,
+            domain: None,
             browser: None,
+            duplicates: DuplicateNameDetector::default(),
+            termination: Termination::default(),
+            dispatcher: Dispatcher::default(),
         }
     }
 
     fn invoke_callback(&self, result: Result<BrowserEvent>) {
         if let Some(f) = &self.service_callback {
-            f(result, self.user_context.clone());
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
         } else {
             warn!("attempted to invoke browser callback but none was set");
         }
     }
+
+    fn invoke_discovered(&mut self, service: ServiceDiscovery) {
+        let duplicate = self.duplicates.observe(&service);
+
+        self.invoke_callback(Ok(BrowserEvent::Add(service)));
+
+        if let Some(duplicate) = duplicate {
+            self.invoke_callback(Ok(BrowserEvent::DuplicateNameDetected(Box::new(duplicate))));
+        }
+    }
+
+    fn terminate(&self, error: Error) {
+        let event = match &error {
+            Error::MdnsSystemError { code, .. } if *code == avahi_sys::AVAHI_ERR_DISCONNECTED => {
+                BrowserEvent::Stopped
+            }
+            e => BrowserEvent::Failed(e.to_string()),
+        };
+
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Ok(event));
+    }
 }
 
 impl fmt::Debug for AvahiBrowserContext {
@@ -159,7 +227,7 @@ unsafe extern "C" fn client_callback(
     let context = AvahiBrowserContext::from_raw(userdata);
 
     if state == avahi_sys::AvahiClientState_AVAHI_CLIENT_FAILURE {
-        context.invoke_callback(Err(avahi_util::get_last_error(client).into()));
+        context.terminate(avahi_util::get_last_error(client));
     }
 }
 
@@ -169,7 +237,7 @@ unsafe fn create_browser(context: &mut AvahiBrowserContext) -> Result<()> {
             .interface(context.interface_index)
             .protocol(avahi_sys::AVAHI_PROTO_UNSPEC)
             .kind(context.kind.as_ptr())
-            .domain(ptr::null_mut())
+            .domain(context.domain.as_ref().map(|d| d.as_ptr()).unwrap_or_null())
             .flags(0)
             .callback(Some(browse_callback))
             .userdata(context.as_raw())
@@ -184,29 +252,43 @@ unsafe fn create_browser(context: &mut AvahiBrowserContext) -> Result<()> {
 }
 
 unsafe extern "C" fn browse_callback(
-    _browser: *mut AvahiServiceBrowser,
+    browser: *mut AvahiServiceBrowser,
     interface: AvahiIfIndex,
     protocol: AvahiProtocol,
     event: AvahiBrowserEvent,
     name: *const c_char,
     kind: *const c_char,
     domain: *const c_char,
-    _flags: AvahiLookupResultFlags,
+    flags: AvahiLookupResultFlags,
     userdata: *mut c_void,
 ) {
     let context = AvahiBrowserContext::from_raw(userdata);
 
     match event {
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_NEW if !context.auto_resolve => {
+            handle_browser_found(context, interface, name, kind, domain);
+        }
         avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_NEW => {
-            if let Err(e) = handle_browser_new(context, interface, protocol, name, kind, domain) {
+            // resolver results do not reliably carry the local flag, so the browse result's is kept
+            let local = avahi_util::is_local_result(flags);
+
+            if let Err(e) =
+                handle_browser_new(context, interface, protocol, name, kind, domain, local)
+            {
                 context.invoke_callback(Err(e));
             }
         }
-        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_FAILURE => {
-            context.invoke_callback(Err(Error::BrowserError("browser failure".into())))
-        }
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_FAILURE => context.terminate(
+            avahi_util::get_last_error(avahi_sys::avahi_service_browser_get_client(browser)),
+        ),
         avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_REMOVE => {
-            handle_browser_remove(context, name, kind, domain);
+            handle_browser_remove(context, interface, protocol, name, kind, domain);
+        }
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_ALL_FOR_NOW => {
+            context.invoke_callback(Ok(BrowserEvent::AllForNow));
+        }
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_CACHE_EXHAUSTED => {
+            context.invoke_callback(Ok(BrowserEvent::CacheExhausted));
         }
         _ => {}
     };
@@ -219,6 +301,7 @@ unsafe fn handle_browser_new(
     name: *const c_char,
     kind: *const c_char,
     domain: *const c_char,
+    local: bool,
 ) -> Result<()> {
     let raw_context = context.as_raw();
 
@@ -227,7 +310,7 @@ unsafe fn handle_browser_new(
         .as_ref()
         .ok_or(Error::BrowserError("expected initialized client".into()))?;
 
-    context.resolvers.insert(ManagedAvahiServiceResolver::new(
+    let resolver = ManagedAvahiServiceResolver::new(
         ManagedAvahiServiceResolverParams::builder()
             .client(client.clone())
             .interface(interface)
@@ -241,13 +324,73 @@ unsafe fn handle_browser_new(
             .userdata(raw_context)
             .build()
             .map_err(Error::BrowserError)?,
-    )?);
+    )?;
+
+    let raw_resolver = resolver.inner;
+    context.resolvers.insert(resolver, local);
+
+    if let Some(timeout) = context.resolve_timeout {
+        let poll = context
+            .poll
+            .as_ref()
+            .ok_or(Error::BrowserError("expected initialized poll".into()))?;
+
+        let timeout = ManagedAvahiTimeout::new(
+            ManagedAvahiTimeoutParams::builder()
+                .poll(poll.clone())
+                .timeout(timeout)
+                .callback(Some(resolve_timeout_callback))
+                .userdata(raw_context)
+                .build()
+                .map_err(Error::BrowserError)?,
+        )?;
+
+        let instance = ServiceInstance::builder()
+            .name(c_str::copy_raw(name))
+            .kind(c_str::copy_raw(kind))
+            .domain(c_str::copy_raw(domain))
+            .interface(avahi_util::interface_from_index(interface))
+            .build()
+            .expect("could not build ServiceInstance");
+
+        context
+            .resolvers
+            .set_timeout(raw_resolver, timeout, instance);
+    }
 
     Ok(())
 }
 
+unsafe extern "C" fn resolve_timeout_callback(timeout: *mut AvahiTimeout, userdata: *mut c_void) {
+    let context = AvahiBrowserContext::from_raw(userdata);
+
+    if let Some(instance) = context.resolvers.remove_timed_out(timeout) {
+        context.invoke_callback(Ok(BrowserEvent::ResolveTimedOut(instance)));
+    }
+}
+
+unsafe fn handle_browser_found(
+    ctx: &mut AvahiBrowserContext,
+    interface: AvahiIfIndex,
+    name: *const c_char,
+    kind: *const c_char,
+    domain: *const c_char,
+) {
+    ctx.invoke_callback(Ok(BrowserEvent::Found(
+        ServiceInstance::builder()
+            .name(c_str::copy_raw(name))
+            .kind(c_str::copy_raw(kind))
+            .domain(c_str::copy_raw(domain))
+            .interface(avahi_util::interface_from_index(interface))
+            .build()
+            .expect("could not build ServiceInstance"),
+    )));
+}
+
 unsafe fn handle_browser_remove(
     ctx: &mut AvahiBrowserContext,
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
     name: *const c_char,
     regtype: *const c_char,
     domain: *const c_char,
@@ -256,11 +399,16 @@ unsafe fn handle_browser_remove(
     let regtype = c_str::raw_to_str(regtype);
     let domain = c_str::raw_to_str(domain);
 
+    let interface = avahi_util::interface_from_index(interface);
+    ctx.duplicates.remove(name, domain, interface);
+
     ctx.invoke_callback(Ok(BrowserEvent::Remove(
         ServiceRemoval::builder()
             .name(name.to_string())
             .kind(regtype.to_string())
             .domain(domain.to_string())
+            .interface(interface)
+            .protocol(avahi_util::protocol_from_avahi(protocol))
             .build()
             .expect("could not build ServiceRemoval"),
     )));
@@ -268,8 +416,8 @@ unsafe fn handle_browser_remove(
 
 unsafe extern "C" fn resolve_callback(
     resolver: *mut AvahiServiceResolver,
-    _interface: AvahiIfIndex,
-    _protocol: AvahiProtocol,
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
     event: AvahiResolverEvent,
     name: *const c_char,
     kind: *const c_char,
@@ -278,7 +426,7 @@ unsafe extern "C" fn resolve_callback(
     addr: *const AvahiAddress,
     port: u16,
     txt: *mut AvahiStringList,
-    _flags: AvahiLookupResultFlags,
+    flags: AvahiLookupResultFlags,
     userdata: *mut c_void,
 ) {
     let name = c_str::raw_to_str(name);
@@ -286,6 +434,7 @@ unsafe extern "C" fn resolve_callback(
     let domain = c_str::raw_to_str(domain);
 
     let context = AvahiBrowserContext::from_raw(userdata);
+    let local_host = context.resolvers.is_local(resolver) || avahi_util::is_local_result(flags);
 
     match event {
         avahi_sys::AvahiResolverEvent_AVAHI_RESOLVER_FAILURE => {
@@ -300,6 +449,8 @@ unsafe extern "C" fn resolve_callback(
         avahi_sys::AvahiResolverEvent_AVAHI_RESOLVER_FOUND => {
             let result = handle_resolver_found(
                 context,
+                interface,
+                protocol,
                 c_str::raw_to_str(host_name),
                 addr,
                 name,
@@ -307,6 +458,7 @@ unsafe extern "C" fn resolve_callback(
                 domain,
                 port,
                 txt,
+                local_host,
             );
 
             if let Err(e) = result {
@@ -321,7 +473,9 @@ unsafe extern "C" fn resolve_callback(
 
 #[allow(clippy::too_many_arguments)]
 unsafe fn handle_resolver_found(
-    context: &AvahiBrowserContext,
+    context: &mut AvahiBrowserContext,
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
     host_name: &str,
     addr: *const AvahiAddress,
     name: &str,
@@ -329,8 +483,44 @@ unsafe fn handle_resolver_found(
     domain: &str,
     port: u16,
     txt: *mut AvahiStringList,
+    local_host: bool,
 ) -> Result<()> {
+    let result = resolved_discovery(
+        interface, protocol, host_name, addr, name, kind, domain, port, txt, local_host,
+    )?;
+
+    debug!("Service resolved: {:?}", result);
+
+    context.invoke_discovered(result);
+
+    Ok(())
+}
+
+/// Builds the `ServiceDiscovery` for the result of an `AvahiServiceResolver`.
+#[allow(clippy::too_many_arguments)]
+pub(super) unsafe fn resolved_discovery(
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
+    host_name: &str,
+    addr: *const AvahiAddress,
+    name: &str,
+    kind: &str,
+    domain: &str,
+    port: u16,
+    txt: *mut AvahiStringList,
+    local_host: bool,
+) -> Result<ServiceDiscovery> {
     let address = avahi_util::avahi_address_to_string(addr);
+    let interface = avahi_util::interface_from_index(interface);
+
+    let addresses = match address.parse() {
+        Ok(address) => vec![ServiceAddress::builder()
+            .address(address)
+            .interface(interface)
+            .build()
+            .map_err(Error::BrowserError)?],
+        Err(_) => vec![],
+    };
 
     let txt = if txt.is_null() {
         None
@@ -338,7 +528,7 @@ unsafe fn handle_resolver_found(
         Some(TxtRecord::from(ManagedAvahiStringList::clone_raw(txt)))
     };
 
-    let result = ServiceDiscovery::builder()
+    ServiceDiscovery::builder()
         .name(name.to_string())
         .service_type(ServiceType::from_str(kind)?)
         .domain(domain.to_string())
@@ -346,12 +536,60 @@ unsafe fn handle_resolver_found(
         .address(address)
         .port(port)
         .txt(txt)
+        .local_host(local_host)
+        .interface(interface)
+        .protocol(avahi_util::protocol_from_avahi(protocol))
+        .addresses(addresses)
         .build()
-        .map_err(Error::BrowserError)?;
+        .map_err(Error::BrowserError)
+}
 
-    debug!("Service resolved: {:?}", result);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IpProtocol;
+    use avahi_sys::{AvahiAddress__bindgen_ty_1, AvahiIPv6Address, AVAHI_PROTO_INET6};
+    use std::net::Ipv6Addr;
+    use std::ptr;
+
+    #[test]
+    fn resolved_discovery_lists_address() {
+        let addr = AvahiAddress {
+            proto: AVAHI_PROTO_INET6,
+            data: AvahiAddress__bindgen_ty_1 {
+                ipv6: AvahiIPv6Address {
+                    address: [
+                        0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56, 0x78,
+                        0x9a, 0xbc, 0xde, 0xf0,
+                    ],
+                },
+            },
+        };
+
+        let discovery = unsafe {
+            resolved_discovery(
+                3,
+                AVAHI_PROTO_INET6,
+                "foo.local",
+                &addr,
+                "foo",
+                "_http._tcp",
+                "local",
+                8080,
+                ptr::null_mut(),
+                false,
+            )
+        }
+        .unwrap();
 
-    context.invoke_callback(Ok(BrowserEvent::Add(result)));
+        let expected = ServiceAddress::builder()
+            .address(Ipv6Addr::new(0xfe80, 0, 0, 0, 0x1234, 0x5678, 0x9abc, 0xdef0).into())
+            .interface(NetworkInterface::AtIndex(3))
+            .build()
+            .unwrap();
 
-    Ok(())
+        assert_eq!(discovery.address(), "fe80::1234:5678:9abc:def0");
+        assert_eq!(discovery.addresses(), &vec![expected]);
+        assert_eq!(discovery.protocol(), &IpProtocol::V6);
+    }
 }
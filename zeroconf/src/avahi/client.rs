@@ -7,8 +7,9 @@ use super::poll::ManagedAvahiSimplePoll;
 use crate::ffi::c_str;
 use crate::{Error, Result};
 use avahi_sys::{
-    avahi_client_free, avahi_client_get_host_name, avahi_client_new, avahi_simple_poll_get,
-    AvahiClient, AvahiClientCallback, AvahiClientFlags,
+    avahi_client_free, avahi_client_get_domain_name, avahi_client_get_host_name,
+    avahi_client_get_state, avahi_client_new, avahi_simple_poll_get, AvahiClient,
+    AvahiClientCallback, AvahiClientFlags, AvahiClientState,
 };
 use libc::{c_int, c_void};
 
@@ -65,6 +66,28 @@ impl ManagedAvahiClient {
     pub unsafe fn host_name<'a>(&self) -> Result<&'a str> {
         get_host_name(self.inner)
     }
+
+    /// Delegate function for [`avahi_client_get_state()`].
+    ///
+    /// [`avahi_client_get_state()`]: https://avahi.org/doxygen/html/client_8h.html
+    ///
+    /// # Safety
+    /// This function is unsafe because of the raw pointer dereference.
+    pub unsafe fn state(&self) -> AvahiClientState {
+        avahi_client_get_state(self.inner)
+    }
+
+    /// Delegate function for [`avahi_client_get_domain_name()`].
+    ///
+    /// Returns the default domain services are published and browsed in, usually `local`.
+    ///
+    /// [`avahi_client_get_domain_name()`]: https://avahi.org/doxygen/html/client_8h.html
+    ///
+    /// # Safety
+    /// This function is unsafe because of the raw pointer dereference.
+    pub unsafe fn domain_name<'a>(&self) -> Result<&'a str> {
+        get_domain_name(self.inner)
+    }
 }
 
 impl Drop for ManagedAvahiClient {
@@ -99,3 +122,14 @@ pub(super) unsafe fn get_host_name<'a>(client: *mut AvahiClient) -> Result<&'a s
         Err(avahi_util::get_last_error(client))
     }
 }
+
+pub(super) unsafe fn get_domain_name<'a>(client: *mut AvahiClient) -> Result<&'a str> {
+    assert_not_null!(client);
+    let domain_name = avahi_client_get_domain_name(client);
+
+    if !domain_name.is_null() {
+        Ok(c_str::raw_to_str(domain_name))
+    } else {
+        Err(avahi_util::get_last_error(client))
+    }
+}
@@ -0,0 +1,251 @@
+//! Avahi implementation for cross-platform domain browser
+
+use super::avahi_util;
+use super::client::{ManagedAvahiClient, ManagedAvahiClientParams};
+use super::poll::ManagedAvahiSimplePoll;
+use super::raw_domain_browser::{ManagedAvahiDomainBrowser, ManagedAvahiDomainBrowserParams};
+use crate::event_loop::{Dispatcher, Termination};
+use crate::ffi::{c_str, AsRaw, FromRaw};
+use crate::prelude::*;
+use crate::{
+    Domain, DomainBrowserCallback, DomainEvent, DomainKind, Error, EventLoop, NetworkInterface,
+    Result,
+};
+use avahi_sys::{
+    AvahiBrowserEvent, AvahiClient, AvahiClientFlags, AvahiClientState, AvahiDomainBrowser,
+    AvahiDomainBrowserType, AvahiIfIndex, AvahiLookupResultFlags, AvahiProtocol,
+};
+use libc::{c_char, c_void};
+use std::any::Any;
+use std::fmt;
+use std::ptr;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct AvahiMdnsDomainBrowser {
+    context: Box<AvahiDomainBrowserContext>,
+    client: Option<Arc<ManagedAvahiClient>>,
+    poll: Option<Arc<ManagedAvahiSimplePoll>>,
+}
+
+impl TMdnsDomainBrowser for AvahiMdnsDomainBrowser {
+    fn new(kind: DomainKind) -> Self {
+        Self {
+            client: None,
+            poll: None,
+            context: Box::new(AvahiDomainBrowserContext::new(kind)),
+        }
+    }
+
+    fn kind(&self) -> DomainKind {
+        self.context.kind
+    }
+
+    fn set_network_interface(&mut self, interface: NetworkInterface) {
+        self.context.interface_index = avahi_util::interface_index(interface);
+    }
+
+    fn network_interface(&self) -> NetworkInterface {
+        avahi_util::interface_from_index(self.context.interface_index)
+    }
+
+    fn set_domain_callback(&mut self, domain_callback: Box<DomainBrowserCallback>) {
+        self.context.domain_callback = Some(domain_callback);
+    }
+
+    fn set_context(&mut self, context: Box<dyn Any>) {
+        self.context.user_context = Some(Arc::from(context));
+    }
+
+    fn context(&self) -> Option<&dyn Any> {
+        self.context.user_context.as_ref().map(|c| c.as_ref())
+    }
+
+    fn browse_domains(&mut self) -> Result<EventLoop> {
+        debug!("Browsing domains: {:?}", self);
+
+        self.poll = Some(Arc::new(
+            unsafe { ManagedAvahiSimplePoll::new() }.ok_or_else(|| {
+                Error::BrowserError("could not initialize AvahiSimplePoll".into())
+            })?,
+        ));
+
+        let poll = self
+            .poll
+            .as_ref()
+            .ok_or(Error::BrowserError("could not get poll as ref".into()))?
+            .clone();
+
+        let client_params = ManagedAvahiClientParams::builder()
+            .poll(poll)
+            .flags(AvahiClientFlags(0))
+            .callback(Some(client_callback))
+            .userdata(self.context.as_raw())
+            .build()
+            .map_err(Error::BrowserError)?;
+
+        self.client = Some(Arc::new(unsafe { ManagedAvahiClient::new(client_params) }?));
+
+        self.context.client.clone_from(&self.client);
+
+        unsafe {
+            if let Err(e) = create_browser(&mut self.context) {
+                self.context.invoke_callback(Err(e));
+            }
+        }
+
+        Ok(EventLoop::new(
+            self.poll
+                .as_ref()
+                .ok_or(Error::BrowserError("could not get poll as ref".into()))?
+                .clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
+        ))
+    }
+}
+
+#[derive(FromRaw, AsRaw)]
+struct AvahiDomainBrowserContext {
+    client: Option<Arc<ManagedAvahiClient>>,
+    browser: Option<ManagedAvahiDomainBrowser>,
+    kind: DomainKind,
+    interface_index: AvahiIfIndex,
+    domain_callback: Option<Box<DomainBrowserCallback>>,
+    user_context: Option<Arc<dyn Any>>,
+    termination: Termination,
+    dispatcher: Dispatcher,
+}
+
+impl AvahiDomainBrowserContext {
+    fn new(kind: DomainKind) -> Self {
+        Self {
+            client: None,
+            browser: None,
+            kind,
+            interface_index: avahi_sys::AVAHI_IF_UNSPEC,
+            domain_callback: None,
+            user_context: None,
+            termination: Termination::default(),
+            dispatcher: Dispatcher::default(),
+        }
+    }
+
+    fn invoke_callback(&self, result: Result<DomainEvent>) {
+        if let Some(f) = &self.domain_callback {
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
+        } else {
+            warn!("attempted to invoke domain browser callback but none was set");
+        }
+    }
+
+    fn terminate(&self, error: Error) {
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Err(error));
+    }
+}
+
+impl fmt::Debug for AvahiDomainBrowserContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AvahiDomainBrowserContext")
+            .field("kind", &self.kind)
+            .field("interface_index", &self.interface_index)
+            .finish()
+    }
+}
+
+unsafe extern "C" fn client_callback(
+    client: *mut AvahiClient,
+    state: AvahiClientState,
+    userdata: *mut c_void,
+) {
+    let context = AvahiDomainBrowserContext::from_raw(userdata);
+
+    if state == avahi_sys::AvahiClientState_AVAHI_CLIENT_FAILURE {
+        context.terminate(avahi_util::get_last_error(client));
+    }
+}
+
+unsafe fn create_browser(context: &mut AvahiDomainBrowserContext) -> Result<()> {
+    context.browser = Some(ManagedAvahiDomainBrowser::new(
+        ManagedAvahiDomainBrowserParams::builder()
+            .interface(context.interface_index)
+            .protocol(avahi_sys::AVAHI_PROTO_UNSPEC)
+            .domain(ptr::null())
+            .kind(browser_type(context.kind))
+            .flags(0)
+            .callback(Some(browse_callback))
+            .userdata(context.as_raw())
+            .client(Arc::clone(context.client.as_ref().ok_or(
+                Error::BrowserError("could not get client as ref".into()),
+            )?))
+            .build()
+            .map_err(Error::BrowserError)?,
+    )?);
+
+    Ok(())
+}
+
+/// Returns the `AvahiDomainBrowserType` that lists domains of the specified kind.
+fn browser_type(kind: DomainKind) -> AvahiDomainBrowserType {
+    match kind {
+        DomainKind::Browse => avahi_sys::AvahiDomainBrowserType_AVAHI_DOMAIN_BROWSER_BROWSE,
+        DomainKind::Registration => avahi_sys::AvahiDomainBrowserType_AVAHI_DOMAIN_BROWSER_REGISTER,
+    }
+}
+
+unsafe extern "C" fn browse_callback(
+    _browser: *mut AvahiDomainBrowser,
+    interface: AvahiIfIndex,
+    _protocol: AvahiProtocol,
+    event: AvahiBrowserEvent,
+    domain: *const c_char,
+    _flags: AvahiLookupResultFlags,
+    userdata: *mut c_void,
+) {
+    let context = AvahiDomainBrowserContext::from_raw(userdata);
+
+    let domain = || {
+        Domain::builder()
+            .name(c_str::raw_to_str(domain).to_string())
+            .interface(avahi_util::interface_from_index(interface))
+            .build()
+            .expect("could not build Domain")
+    };
+
+    match event {
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_NEW => {
+            context.invoke_callback(Ok(DomainEvent::DomainAdded(domain())));
+        }
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_REMOVE => {
+            context.invoke_callback(Ok(DomainEvent::DomainRemoved(domain())));
+        }
+        avahi_sys::AvahiBrowserEvent_AVAHI_BROWSER_FAILURE => {
+            let error = match &context.client {
+                Some(client) => avahi_util::get_last_error(client.inner),
+                None => Error::BrowserError("domain browser failed".into()),
+            };
+
+            context.terminate(error);
+        }
+        _ => {}
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browser_type_matches_kind() {
+        assert_eq!(
+            browser_type(DomainKind::Browse),
+            avahi_sys::AvahiDomainBrowserType_AVAHI_DOMAIN_BROWSER_BROWSE
+        );
+        assert_eq!(
+            browser_type(DomainKind::Registration),
+            avahi_sys::AvahiDomainBrowserType_AVAHI_DOMAIN_BROWSER_REGISTER
+        );
+    }
+}
@@ -8,9 +8,10 @@ use crate::ffi::UnwrapMutOrNull;
 use crate::Result;
 use avahi_sys::{
     avahi_entry_group_add_service_strlst, avahi_entry_group_add_service_subtype,
-    avahi_entry_group_commit, avahi_entry_group_free, avahi_entry_group_is_empty,
-    avahi_entry_group_new, avahi_entry_group_reset, AvahiClient, AvahiEntryGroup,
-    AvahiEntryGroupCallback, AvahiIfIndex, AvahiProtocol, AvahiPublishFlags,
+    avahi_entry_group_commit, avahi_entry_group_free, avahi_entry_group_get_state,
+    avahi_entry_group_is_empty, avahi_entry_group_new, avahi_entry_group_reset, AvahiClient,
+    AvahiEntryGroup, AvahiEntryGroupCallback, AvahiEntryGroupState, AvahiIfIndex, AvahiProtocol,
+    AvahiPublishFlags,
 };
 use libc::{c_char, c_void};
 
@@ -147,12 +148,28 @@ impl ManagedAvahiEntryGroup {
 
     /// Delegate function for [`avahi_entry_group_reset()`].
     ///
+    /// Also propagates any error returned into a `Result`. After a successful reset the group is
+    /// empty and may be populated and committed again.
+    ///
     /// [`avahi_entry_group_reset()`]: https://avahi.org/doxygen/html/publish_8h.html#a1293bbccf878dbeb9916660022bc71b2
     ///
     /// # Safety
     /// This function is unsafe because of the call to `avahi_entry_group_reset()`.
-    pub unsafe fn reset(&mut self) {
-        avahi_entry_group_reset(self.inner);
+    pub unsafe fn reset(&mut self) -> Result<()> {
+        avahi_util::sys_exec(
+            || avahi_entry_group_reset(self.inner),
+            "could not reset service",
+        )
+    }
+
+    /// Delegate function for [`avahi_entry_group_get_state()`].
+    ///
+    /// [`avahi_entry_group_get_state()`]: https://avahi.org/doxygen/html/publish_8h.html
+    ///
+    /// # Safety
+    /// This function is unsafe because of the call to `avahi_entry_group_get_state()`.
+    pub unsafe fn get_state(&self) -> AvahiEntryGroupState {
+        avahi_entry_group_get_state(self.inner) as AvahiEntryGroupState
     }
 
     /// Delegate function for [`avahi_entry_group_get_client()`].
@@ -217,3 +234,88 @@ pub struct AddServiceSubtypeParams {
     domain: *const c_char,
     subtype: *const c_char,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avahi::client::ManagedAvahiClientParams;
+    use crate::avahi::poll::ManagedAvahiSimplePoll;
+    use crate::prelude::*;
+    use avahi_sys::{
+        AvahiClientFlags, AvahiEntryGroupState_AVAHI_ENTRY_GROUP_UNCOMMITED as UNCOMMITED,
+    };
+    use std::ffi::CString;
+    use std::ptr;
+    use std::time::{Duration, Instant};
+
+    unsafe fn add_service(group: &mut ManagedAvahiEntryGroup, name: &CString, kind: &CString) {
+        group
+            .add_service(
+                AddServiceParams::builder()
+                    .interface(avahi_sys::AVAHI_IF_UNSPEC)
+                    .protocol(avahi_sys::AVAHI_PROTO_UNSPEC)
+                    .flags(0)
+                    .name(name.as_ptr())
+                    .kind(kind.as_ptr())
+                    .domain(ptr::null())
+                    .host(ptr::null())
+                    .port(8080)
+                    .txt(None)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn reset_returns_group_to_uncommitted() {
+        crate::tests::setup();
+
+        let name = c_string!("entry_group_reset_returns_group_to_uncommitted");
+        let kind = c_string!("_http._tcp");
+
+        unsafe {
+            let poll = Arc::new(ManagedAvahiSimplePoll::new().unwrap());
+
+            let client = ManagedAvahiClient::new(
+                ManagedAvahiClientParams::builder()
+                    .poll(poll.clone())
+                    .flags(AvahiClientFlags(0))
+                    .callback(None)
+                    .userdata(ptr::null_mut())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+            let mut group = ManagedAvahiEntryGroup::new(
+                ManagedAvahiEntryGroupParams::builder()
+                    .client(Arc::new(client))
+                    .callback(None)
+                    .userdata(ptr::null_mut())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+            add_service(&mut group, &name, &kind);
+            group.commit().unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(5);
+
+            while group.get_state() == UNCOMMITED && Instant::now() < deadline {
+                poll.iterate(Duration::from_millis(100)).unwrap();
+            }
+
+            assert_ne!(group.get_state(), UNCOMMITED);
+
+            group.reset().unwrap();
+
+            assert_eq!(group.get_state(), UNCOMMITED);
+            assert!(group.is_empty());
+
+            add_service(&mut group, &name, &kind);
+            group.commit().unwrap();
+        }
+    }
+}
@@ -1,14 +1,30 @@
 //! Event loop for running a `MdnsService` or `MdnsBrowser`.
 
 use super::poll::ManagedAvahiSimplePoll;
-use crate::event_loop::TEventLoop;
+use crate::event_loop::{Dispatcher, TEventLoop, Termination, Waker};
 use crate::Result;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+/// Event loop driving an `AvahiSimplePoll`.
+///
+/// Unlike the Bonjour event loop, this does not implement `AsRawFd`, as the descriptors and
+/// timeouts the poll watches are internal to Avahi and change over time.
 #[derive(new)]
 pub struct AvahiEventLoop {
     poll: Arc<ManagedAvahiSimplePoll>,
+    termination: Termination,
+    dispatcher: Dispatcher,
+    #[new(value = "Instant::now()")]
+    started: Instant,
+}
+
+impl AvahiEventLoop {
+    /// Returns a `Waker` that interrupts a `poll()` of this event loop from another thread.
+    pub(crate) fn waker(&self) -> Option<Waker> {
+        let poll = self.poll.clone();
+        Some(Waker::new(move || unsafe { poll.wakeup() }))
+    }
 }
 
 impl TEventLoop for AvahiEventLoop {
@@ -19,6 +35,23 @@ impl TEventLoop for AvahiEventLoop {
     /// does not respect the `timeout` parameter, the `timeout` passed
     /// here will have no effect -- ie will return immediately.
     fn poll(&self, timeout: Duration) -> Result<()> {
+        self.termination.check()?;
         unsafe { self.poll.iterate(timeout) }
     }
+
+    fn dispatch_count(&self) -> usize {
+        self.dispatcher.count()
+    }
+
+    fn set_slow_callback_threshold(&self, threshold: Option<Duration>) {
+        self.dispatcher.set_slow_threshold(threshold);
+    }
+
+    fn last_error(&self) -> Option<String> {
+        self.termination.reason()
+    }
+
+    fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
 }
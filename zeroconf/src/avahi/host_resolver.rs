@@ -0,0 +1,224 @@
+//! Rust friendly `AvahiHostNameResolver` wrappers/helpers
+
+use super::avahi_util;
+use super::client::{ManagedAvahiClient, ManagedAvahiClientParams};
+use super::poll::ManagedAvahiSimplePoll;
+use crate::ffi::{c_str, AsRaw, FromRaw};
+use crate::host::OTHER_FAMILY_GRACE;
+use crate::prelude::*;
+use crate::{Error, Result};
+use avahi_sys::{
+    avahi_host_name_resolver_free, avahi_host_name_resolver_new, AvahiAddress, AvahiClientFlags,
+    AvahiHostNameResolver, AvahiHostNameResolverCallback, AvahiIfIndex, AvahiLookupFlags,
+    AvahiLookupResultFlags, AvahiProtocol, AvahiResolverEvent,
+};
+use libc::{c_char, c_void};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Wraps the `AvahiHostNameResolver` type from the raw Avahi bindings.
+///
+/// This struct allocates a new `*mut AvahiHostNameResolver` when
+/// `ManagedAvahiHostNameResolver::new()` is invoked and calls the Avahi function responsible for
+/// freeing the resolver on `trait Drop`.
+#[derive(Debug)]
+pub struct ManagedAvahiHostNameResolver {
+    inner: *mut AvahiHostNameResolver,
+    _client: Arc<ManagedAvahiClient>,
+}
+
+impl ManagedAvahiHostNameResolver {
+    /// Initializes the underlying `*mut AvahiHostNameResolver` and verifies it was created;
+    /// returning `Err(String)` if unsuccessful.
+    ///
+    /// # Safety
+    /// This function is unsafe because of the raw pointer dereference.
+    pub unsafe fn new(
+        ManagedAvahiHostNameResolverParams {
+            client,
+            interface,
+            protocol,
+            name,
+            aprotocol,
+            flags,
+            callback,
+            userdata,
+        }: ManagedAvahiHostNameResolverParams,
+    ) -> Result<Self> {
+        let inner = avahi_host_name_resolver_new(
+            client.inner,
+            interface,
+            protocol,
+            name,
+            aprotocol,
+            flags,
+            callback,
+            userdata,
+        );
+
+        if inner.is_null() {
+            Err(avahi_util::get_last_error(client.inner))
+        } else {
+            Ok(Self {
+                inner,
+                _client: client,
+            })
+        }
+    }
+}
+
+impl Drop for ManagedAvahiHostNameResolver {
+    fn drop(&mut self) {
+        unsafe { avahi_host_name_resolver_free(self.inner) };
+    }
+}
+
+/// Holds parameters for initializing a new `ManagedAvahiHostNameResolver` with
+/// `ManagedAvahiHostNameResolver::new()`.
+///
+/// See [`avahi_host_name_resolver_new()`] for more information about these parameters.
+///
+/// [`avahi_host_name_resolver_new()`]: https://avahi.org/doxygen/html/lookup_8h.html
+#[derive(Builder, BuilderDelegate)]
+pub struct ManagedAvahiHostNameResolverParams {
+    client: Arc<ManagedAvahiClient>,
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
+    name: *const c_char,
+    aprotocol: AvahiProtocol,
+    flags: AvahiLookupFlags,
+    callback: AvahiHostNameResolverCallback,
+    userdata: *mut c_void,
+}
+
+#[derive(FromRaw, AsRaw)]
+struct HostNameResolverContext {
+    addresses: Vec<IpAddr>,
+    pending: usize,
+    error: Option<Error>,
+}
+
+/// Resolves the specified host name over mDNS, waiting up to `timeout` for an answer.
+///
+/// IPv4 and IPv6 addresses are resolved separately. Once either has been resolved, the other is
+/// waited for only briefly, so that hosts with a single address family do not wait out the full
+/// `timeout`.
+pub fn resolve_host_name(host_name: &str, timeout: Duration) -> Result<Vec<IpAddr>> {
+    let mut deadline = Instant::now() + timeout;
+    let families = [avahi_sys::AVAHI_PROTO_INET, avahi_sys::AVAHI_PROTO_INET6];
+
+    let mut context = Box::new(HostNameResolverContext {
+        addresses: Vec::new(),
+        pending: families.len(),
+        error: None,
+    });
+
+    let poll = Arc::new(
+        unsafe { ManagedAvahiSimplePoll::new() }
+            .ok_or_else(|| Error::BrowserError("could not initialize AvahiSimplePoll".into()))?,
+    );
+
+    let client = Arc::new(unsafe {
+        ManagedAvahiClient::new(
+            ManagedAvahiClientParams::builder()
+                .poll(poll.clone())
+                .flags(AvahiClientFlags(0))
+                .callback(None)
+                .userdata(std::ptr::null_mut())
+                .build()
+                .map_err(Error::BrowserError)?,
+        )
+    }?);
+
+    let name = c_string!(host_name);
+
+    let _resolvers = families
+        .iter()
+        .map(|aprotocol| unsafe {
+            ManagedAvahiHostNameResolver::new(
+                ManagedAvahiHostNameResolverParams::builder()
+                    .client(client.clone())
+                    .interface(avahi_sys::AVAHI_IF_UNSPEC)
+                    .protocol(avahi_sys::AVAHI_PROTO_UNSPEC)
+                    .name(name.as_ptr())
+                    .aprotocol(*aprotocol)
+                    .flags(0)
+                    .callback(Some(resolve_callback))
+                    .userdata(context.as_raw())
+                    .build()
+                    .map_err(Error::BrowserError)?,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // `pending` is decremented by `resolve_callback()` as each resolver finishes
+    loop {
+        if context.pending == 0 {
+            break;
+        }
+
+        let now = Instant::now();
+
+        if !context.addresses.is_empty() {
+            deadline = deadline.min(now + OTHER_FAMILY_GRACE);
+        }
+
+        if now >= deadline {
+            break;
+        }
+
+        unsafe { poll.iterate(deadline - now) }?;
+    }
+
+    if !context.addresses.is_empty() {
+        return Ok(std::mem::take(&mut context.addresses));
+    }
+
+    Err(context.error.take().unwrap_or_else(|| {
+        Error::HostNotFound(format!(
+            "`{}` was not resolved over mDNS within {:?}",
+            host_name, timeout
+        ))
+    }))
+}
+
+unsafe extern "C" fn resolve_callback(
+    _resolver: *mut AvahiHostNameResolver,
+    _interface: AvahiIfIndex,
+    _protocol: AvahiProtocol,
+    event: AvahiResolverEvent,
+    name: *const c_char,
+    addr: *const AvahiAddress,
+    _flags: AvahiLookupResultFlags,
+    userdata: *mut c_void,
+) {
+    let context = HostNameResolverContext::from_raw(userdata);
+
+    context.pending = context.pending.saturating_sub(1);
+
+    match event {
+        avahi_sys::AvahiResolverEvent_AVAHI_RESOLVER_FOUND => {
+            let address = avahi_util::avahi_address_to_string(addr);
+
+            match address.parse() {
+                Ok(address) if !context.addresses.contains(&address) => {
+                    context.addresses.push(address)
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    context.error = Some(Error::BrowserError(format!(
+                        "invalid address `{}` for host name",
+                        address
+                    )))
+                }
+            }
+        }
+        _ => {
+            context.error = Some(Error::HostNotFound(format!(
+                "`{}` could not be resolved over mDNS",
+                c_str::raw_to_str(name)
+            )))
+        }
+    }
+}
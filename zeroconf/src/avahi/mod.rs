@@ -9,11 +9,16 @@
 pub mod avahi_util;
 pub mod browser;
 pub mod client;
+pub mod domain_browser;
 pub mod entry_group;
 pub mod event_loop;
+pub mod host_resolver;
 pub mod poll;
 pub mod raw_browser;
+pub mod raw_domain_browser;
 pub mod resolver;
 pub mod service;
+pub mod service_resolver;
 pub mod string_list;
+pub mod timeout;
 pub mod txt_record;
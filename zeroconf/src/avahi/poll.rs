@@ -4,7 +4,7 @@ use crate::Result;
 use crate::{avahi::avahi_util, error::Error};
 use avahi_sys::{
     avahi_simple_poll_free, avahi_simple_poll_iterate, avahi_simple_poll_loop,
-    avahi_simple_poll_new, AvahiSimplePoll,
+    avahi_simple_poll_new, avahi_simple_poll_wakeup, AvahiSimplePoll,
 };
 use std::{convert::TryInto, time::Duration};
 
@@ -69,6 +69,17 @@ impl ManagedAvahiSimplePoll {
         }
     }
 
+    /// Delegate function for [`avahi_simple_poll_wakeup()`]. Makes a running or the next
+    /// `iterate()` return without waiting for its timeout; may be called from another thread.
+    ///
+    /// [`avahi_simple_poll_wakeup()`]: https://avahi.org/doxygen/html/simple-watch_8h.html
+    ///
+    /// # Safety
+    /// This function is unsafe because of the call to `avahi_simple_poll_wakeup()`.
+    pub unsafe fn wakeup(&self) {
+        avahi_simple_poll_wakeup(self.0);
+    }
+
     pub(super) fn inner(&self) -> *mut AvahiSimplePoll {
         self.0
     }
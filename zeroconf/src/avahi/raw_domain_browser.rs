@@ -0,0 +1,87 @@
+//! Rust friendly `AvahiDomainBrowser` wrappers/helpers
+
+use std::sync::Arc;
+
+use crate::{avahi::avahi_util, Result};
+use avahi_sys::{
+    avahi_domain_browser_free, avahi_domain_browser_new, AvahiDomainBrowser,
+    AvahiDomainBrowserCallback, AvahiDomainBrowserType, AvahiIfIndex, AvahiLookupFlags,
+    AvahiProtocol,
+};
+use libc::{c_char, c_void};
+
+use super::client::ManagedAvahiClient;
+
+/// Wraps the `AvahiDomainBrowser` type from the raw Avahi bindings.
+///
+/// This struct allocates a new `*mut AvahiDomainBrowser` when `ManagedAvahiDomainBrowser::new()`
+/// is invoked and calls the Avahi function responsible for freeing the browser on `trait Drop`.
+#[derive(Debug)]
+pub struct ManagedAvahiDomainBrowser {
+    inner: *mut AvahiDomainBrowser,
+    _client: Arc<ManagedAvahiClient>,
+}
+
+impl ManagedAvahiDomainBrowser {
+    /// Initializes the underlying `*mut AvahiDomainBrowser` and verifies it was created;
+    /// returning `Err(String)` if unsuccessful.
+    ///
+    /// # Safety
+    /// This function is unsafe because of the raw pointer dereference.
+    pub unsafe fn new(
+        ManagedAvahiDomainBrowserParams {
+            client,
+            interface,
+            protocol,
+            domain,
+            kind,
+            flags,
+            callback,
+            userdata,
+        }: ManagedAvahiDomainBrowserParams,
+    ) -> Result<Self> {
+        let inner = avahi_domain_browser_new(
+            client.inner,
+            interface,
+            protocol,
+            domain,
+            kind,
+            flags,
+            callback,
+            userdata,
+        );
+
+        if inner.is_null() {
+            Err(avahi_util::get_last_error(client.inner))
+        } else {
+            Ok(Self {
+                inner,
+                _client: client,
+            })
+        }
+    }
+}
+
+impl Drop for ManagedAvahiDomainBrowser {
+    fn drop(&mut self) {
+        unsafe { avahi_domain_browser_free(self.inner) };
+    }
+}
+
+/// Holds parameters for initializing a new `ManagedAvahiDomainBrowser` with
+/// `ManagedAvahiDomainBrowser::new()`.
+///
+/// See [`avahi_domain_browser_new()`] for more information about these parameters.
+///
+/// [`avahi_domain_browser_new()`]: https://avahi.org/doxygen/html/lookup_8h.html
+#[derive(Builder, BuilderDelegate)]
+pub struct ManagedAvahiDomainBrowserParams {
+    client: Arc<ManagedAvahiClient>,
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
+    domain: *const c_char,
+    kind: AvahiDomainBrowserType,
+    flags: AvahiLookupFlags,
+    callback: AvahiDomainBrowserCallback,
+    userdata: *mut c_void,
+}
@@ -1,14 +1,15 @@
 //! Rust friendly `AvahiServiceResolver` wrappers/helpers
 
-use crate::{avahi::avahi_util, Result};
+use crate::{avahi::avahi_util, Result, ServiceInstance};
 use avahi_sys::{
     avahi_service_resolver_free, avahi_service_resolver_new, AvahiIfIndex, AvahiLookupFlags,
-    AvahiProtocol, AvahiServiceResolver, AvahiServiceResolverCallback,
+    AvahiProtocol, AvahiServiceResolver, AvahiServiceResolverCallback, AvahiTimeout,
 };
 use libc::{c_char, c_void};
 use std::{collections::HashMap, sync::Arc};
 
 use super::client::ManagedAvahiClient;
+use super::timeout::ManagedAvahiTimeout;
 
 /// Wraps the `AvahiServiceResolver` type from the raw Avahi bindings.
 ///
@@ -17,7 +18,7 @@ use super::client::ManagedAvahiClient;
 /// freeing the client on `trait Drop`.
 #[derive(Debug)]
 pub struct ManagedAvahiServiceResolver {
-    inner: *mut AvahiServiceResolver,
+    pub(crate) inner: *mut AvahiServiceResolver,
     _client: Arc<ManagedAvahiClient>,
 }
 
@@ -91,17 +92,70 @@ pub struct ManagedAvahiServiceResolverParams {
     userdata: *mut c_void,
 }
 
+/// The resolvers started by a browser, each with whether the browse result it resolves
+/// originates from the local host and the timeout it is abandoned after, if any.
 #[derive(Default, Debug)]
 pub(crate) struct ServiceResolverSet {
-    resolvers: HashMap<*mut AvahiServiceResolver, ManagedAvahiServiceResolver>,
+    resolvers: HashMap<*mut AvahiServiceResolver, ServiceResolverEntry>,
+}
+
+#[derive(Debug)]
+struct ServiceResolverEntry {
+    // the timeout is declared first so that it is freed before the resolver
+    timeout: Option<(ManagedAvahiTimeout, ServiceInstance)>,
+    _resolver: ManagedAvahiServiceResolver,
+    local: bool,
 }
 
 impl ServiceResolverSet {
-    pub fn insert(&mut self, resolver: ManagedAvahiServiceResolver) {
-        self.resolvers.insert(resolver.inner, resolver);
+    pub fn insert(&mut self, resolver: ManagedAvahiServiceResolver, local: bool) {
+        self.resolvers.insert(
+            resolver.inner,
+            ServiceResolverEntry {
+                timeout: None,
+                _resolver: resolver,
+                local,
+            },
+        );
+    }
+
+    /// Sets the timeout after which the specified resolver is abandoned, along with the instance
+    /// it resolves.
+    pub fn set_timeout(
+        &mut self,
+        raw: *mut AvahiServiceResolver,
+        timeout: ManagedAvahiTimeout,
+        instance: ServiceInstance,
+    ) {
+        if let Some(entry) = self.resolvers.get_mut(&raw) {
+            entry.timeout = Some((timeout, instance));
+        }
+    }
+
+    /// Returns true if the specified resolver was started for a browse result originating from
+    /// the local host.
+    pub fn is_local(&self, raw: *mut AvahiServiceResolver) -> bool {
+        self.resolvers
+            .get(&raw)
+            .map(|entry| entry.local)
+            .unwrap_or(false)
     }
 
     pub fn remove_raw(&mut self, raw: *mut AvahiServiceResolver) {
         self.resolvers.remove(&raw);
     }
+
+    /// Removes the resolver whose timeout has fired, returning the instance it was resolving.
+    pub fn remove_timed_out(&mut self, timeout: *mut AvahiTimeout) -> Option<ServiceInstance> {
+        let raw = self
+            .resolvers
+            .iter()
+            .find(|(_, entry)| matches!(&entry.timeout, Some((t, _)) if t.inner == timeout))
+            .map(|(raw, _)| *raw)?;
+
+        self.resolvers
+            .remove(&raw)
+            .and_then(|entry| entry.timeout)
+            .map(|(_, instance)| instance)
+    }
 }
@@ -6,6 +6,8 @@ use super::entry_group::{
     AddServiceParams, AddServiceSubtypeParams, ManagedAvahiEntryGroup, ManagedAvahiEntryGroupParams,
 };
 use super::poll::ManagedAvahiSimplePoll;
+use super::string_list::ManagedAvahiStringList;
+use crate::event_loop::{Dispatcher, Termination};
 use crate::ffi::{c_str, AsRaw, FromRaw, UnwrapOrNull};
 use crate::prelude::*;
 use crate::{
@@ -63,6 +65,14 @@ impl TMdnsService for AvahiMdnsService {
         self.context.name.as_ref().map(c_str::to_str)
     }
 
+    fn set_auto_rename(&mut self, auto_rename: bool) {
+        self.context.auto_rename = auto_rename
+    }
+
+    fn auto_rename(&self) -> bool {
+        self.context.auto_rename
+    }
+
     fn set_network_interface(&mut self, interface: NetworkInterface) {
         self.context.interface_index = avahi_util::interface_index(interface)
     }
@@ -134,9 +144,19 @@ impl TMdnsService for AvahiMdnsService {
 
         self.context.client.clone_from(&self.client);
 
-        unsafe {
-            if let Err(e) = create_service(&mut self.context) {
-                self.context.invoke_callback(Err(e))
+        // services registered before the daemon is running are published by `client_callback()`
+        // once the client reaches the running state
+        let running = self
+            .client
+            .as_ref()
+            .map(|c| unsafe { c.state() } == avahi_sys::AvahiServerState_AVAHI_SERVER_RUNNING)
+            .unwrap_or(false);
+
+        if running {
+            unsafe {
+                if let Err(e) = create_service(&mut self.context) {
+                    self.context.invoke_callback(Err(e))
+                }
             }
         }
 
@@ -145,6 +165,8 @@ impl TMdnsService for AvahiMdnsService {
                 .as_ref()
                 .ok_or(Error::ServiceError("could not get poll as ref".into()))?
                 .clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
         ))
     }
 }
@@ -153,6 +175,7 @@ impl TMdnsService for AvahiMdnsService {
 struct AvahiServiceContext {
     client: Option<Arc<ManagedAvahiClient>>,
     name: Option<CString>,
+    auto_rename: bool,
     kind: CString,
     sub_types: Vec<CString>,
     port: u16,
@@ -163,6 +186,8 @@ struct AvahiServiceContext {
     host: Option<CString>,
     registered_callback: Option<Box<ServiceRegisteredCallback>>,
     user_context: Option<Arc<dyn Any>>,
+    termination: Termination,
+    dispatcher: Dispatcher,
 }
 
 impl AvahiServiceContext {
@@ -170,6 +195,7 @@ impl AvahiServiceContext {
         Self {
             client: None,
             name: None,
+            auto_rename: true,
             kind,
             port,
             sub_types,
@@ -180,16 +206,24 @@ impl AvahiServiceContext {
             host: None,
             registered_callback: None,
             user_context: None,
+            termination: Termination::default(),
+            dispatcher: Dispatcher::default(),
         }
     }
 
     fn invoke_callback(&self, result: Result<ServiceRegistration>) {
         if let Some(f) = &self.registered_callback {
-            f(result, self.user_context.clone());
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
         } else {
             warn!("attempted to invoke service callback but none was set");
         }
     }
+
+    fn terminate(&self, error: Error) {
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Err(error));
+    }
 }
 
 impl fmt::Debug for AvahiServiceContext {
@@ -211,11 +245,26 @@ unsafe extern "C" fn client_callback(
     let context = AvahiServiceContext::from_raw(userdata);
 
     match state {
+        // `register()` publishes the service itself if the client is already running on creation
+        avahi_sys::AvahiServerState_AVAHI_SERVER_RUNNING if context.client.is_some() => {
+            if let Err(e) = create_service(context) {
+                context.invoke_callback(Err(e))
+            }
+        }
+        avahi_sys::AvahiServerState_AVAHI_SERVER_REGISTERING => {
+            // the daemon is re-registering its host name, withdraw until it is running again
+            if let Some(Err(e)) = context.group.as_mut().map(|g| g.reset()) {
+                context.invoke_callback(Err(e))
+            }
+        }
         avahi_sys::AvahiServerState_AVAHI_SERVER_INVALID
-        | avahi_sys::AvahiServerState_AVAHI_SERVER_COLLISION
-        | avahi_sys::AvahiServerState_AVAHI_SERVER_FAILURE => {
+        | avahi_sys::AvahiServerState_AVAHI_SERVER_COLLISION => {
             context.invoke_callback(Err(avahi_util::get_last_error(client).into()))
         }
+        // the connection to the daemon is lost and the client will not recover
+        avahi_sys::AvahiClientState_AVAHI_CLIENT_FAILURE => {
+            context.terminate(avahi_util::get_last_error(client))
+        }
         _ => {}
     }
 }
@@ -264,6 +313,11 @@ unsafe fn create_service(context: &mut AvahiServiceContext) -> Result<()> {
     add_services(context, &name)
 }
 
+/// Returns the TXT record data to add to the entry group, in the order it is published.
+fn entry_group_txt(txt_record: Option<&TxtRecord>) -> Option<&ManagedAvahiStringList> {
+    txt_record.map(|t| t.inner())
+}
+
 unsafe fn add_services(context: &mut AvahiServiceContext, name: &CStr) -> Result<()> {
     debug!("Adding service: {}", context.kind.to_string_lossy());
 
@@ -281,11 +335,18 @@ unsafe fn add_services(context: &mut AvahiServiceContext, name: &CStr) -> Result
         .domain(context.domain.as_ref().map(|d| d.as_ptr()).unwrap_or_null())
         .host(context.host.as_ref().map(|h| h.as_ptr()).unwrap_or_null())
         .port(context.port)
-        .txt(context.txt_record.as_ref().map(|t| t.inner()))
+        .txt(entry_group_txt(context.txt_record.as_ref()))
         .build()
         .map_err(Error::ServiceError)?;
 
-    group.add_service(params)?;
+    if let Err(e) = group.add_service(params) {
+        return Err(match e {
+            Error::MdnsSystemError { code, .. } if code == avahi_sys::AVAHI_ERR_COLLISION => {
+                Error::NameConflict(name.to_string_lossy().into_owned())
+            }
+            e => e,
+        });
+    }
 
     for sub_type in &context.sub_types {
         debug!("Adding service subtype: {}", sub_type.to_string_lossy());
@@ -324,7 +385,7 @@ unsafe extern "C" fn entry_group_callback(
             context.invoke_callback(handle_group_established(context))
         }
         avahi_sys::AvahiEntryGroupState_AVAHI_ENTRY_GROUP_FAILURE => {
-            context.invoke_callback(Err(avahi_util::get_last_error(client.inner).into()))
+            context.terminate(avahi_util::get_last_error(client.inner))
         }
         avahi_sys::AvahiEntryGroupState_AVAHI_ENTRY_GROUP_COLLISION => {
             let name = context
@@ -333,8 +394,20 @@ unsafe extern "C" fn entry_group_callback(
                 .expect("expected initialized name")
                 .clone();
 
+            if !context.auto_rename {
+                let name = name.to_string_lossy().into_owned();
+                context.invoke_callback(Err(Error::NameConflict(name)));
+                return;
+            }
+
             let new_name = avahi_util::alternative_service_name(name.as_c_str());
-            let result = add_services(context, new_name);
+
+            let result = context
+                .group
+                .as_mut()
+                .ok_or(Error::ServiceError("could not borrow group as mut".into()))
+                .and_then(|group| group.reset())
+                .and_then(|_| add_services(context, new_name));
 
             context.name = Some(new_name.into());
 
@@ -349,20 +422,58 @@ unsafe extern "C" fn entry_group_callback(
 unsafe fn handle_group_established(context: &AvahiServiceContext) -> Result<ServiceRegistration> {
     debug!("Group established");
 
-    let name = c_str::copy_raw(
-        context
-            .name
+    let name = context
+        .name
+        .as_ref()
+        .ok_or(Error::ServiceError("could not get name as ref".into()))?;
+
+    // Bonjour reports the domain without the trailing dot, regardless of how it was specified
+    let domain = match &context.domain {
+        Some(domain) => {
+            let domain = c_str::to_str(domain);
+            domain.strip_suffix('.').unwrap_or(domain).to_string()
+        }
+        None => context
+            .client
             .as_ref()
-            .ok_or(Error::ServiceError("could not get name as ref".into()))?
-            .as_ptr(),
-    );
+            .ok_or(Error::ServiceError("expected initialized client".into()))?
+            .domain_name()?
+            .to_string(),
+    };
+
+    let full_name =
+        avahi_util::join_service_name(name, &context.kind, &c_string!(domain.as_str()))?;
 
     Ok(ServiceRegistration::builder()
-        .name(name)
+        .name(c_str::copy_raw(name.as_ptr()))
         .service_type(ServiceType::from_str(&c_str::copy_raw(
             context.kind.as_ptr(),
         ))?)
-        .domain("local".to_string())
+        .domain(domain)
+        .full_name(full_name)
+        .interface(avahi_util::interface_from_index(context.interface_index))
         .build()
         .map_err(Error::ServiceError)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_group_txt_is_published_in_order() {
+        crate::tests::setup();
+
+        let mut record = TxtRecord::new();
+        record.insert("foo", "1").unwrap();
+        record.insert("txtvers", "1").unwrap();
+        record.insert("rp", "3").unwrap();
+
+        let mut service = AvahiMdnsService::new(ServiceType::new("ipp", "tcp").unwrap(), 631);
+        service.set_txt_record(record.with_order(&["txtvers", "rp"]));
+
+        let bytes = unsafe { entry_group_txt(service.txt_record()).unwrap().serialize() };
+
+        assert_eq!(bytes, b"\x09txtvers=1\x04rp=3\x05foo=1");
+    }
+}
@@ -0,0 +1,353 @@
+//! Avahi implementation for cross-platform resolver
+
+use super::avahi_util;
+use super::browser::resolved_discovery;
+use super::client::{ManagedAvahiClient, ManagedAvahiClientParams};
+use super::poll::ManagedAvahiSimplePoll;
+use super::resolver::{ManagedAvahiServiceResolver, ManagedAvahiServiceResolverParams};
+use crate::event_loop::{Dispatcher, Termination};
+use crate::ffi::{c_str, AsRaw, FromRaw, UnwrapOrNull};
+use crate::prelude::*;
+use crate::resolver::ResolveBatch;
+use crate::{
+    Error, EventLoop, NetworkInterface, Result, ServiceDiscovery, ServiceKey,
+    ServiceResolvedCallback, ServiceType,
+};
+use avahi_sys::{
+    AvahiAddress, AvahiClient, AvahiClientFlags, AvahiClientState, AvahiIfIndex,
+    AvahiLookupResultFlags, AvahiProtocol, AvahiResolverEvent, AvahiServiceResolver,
+    AvahiStringList,
+};
+use libc::{c_char, c_void};
+use std::any::Any;
+use std::ffi::CString;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct AvahiMdnsResolver {
+    context: Box<AvahiResolverContext>,
+    client: Option<Arc<ManagedAvahiClient>>,
+    poll: Option<Arc<ManagedAvahiSimplePoll>>,
+}
+
+impl TMdnsResolver for AvahiMdnsResolver {
+    fn new(service_type: ServiceType, name: &str) -> Self {
+        Self {
+            client: None,
+            poll: None,
+            context: Box::new(AvahiResolverContext::new(
+                c_string!(name),
+                c_string!(avahi_util::format_service_type(&service_type)),
+            )),
+        }
+    }
+
+    fn set_network_interface(&mut self, interface: NetworkInterface) {
+        self.context.interface_index = avahi_util::interface_index(interface);
+    }
+
+    fn network_interface(&self) -> NetworkInterface {
+        avahi_util::interface_from_index(self.context.interface_index)
+    }
+
+    fn set_domain(&mut self, domain: &str) {
+        self.context.domain = c_string!(domain).into()
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.context.domain.as_ref().map(c_str::to_str)
+    }
+
+    fn set_resolved_callback(&mut self, resolved_callback: Box<ServiceResolvedCallback>) {
+        self.context.resolved_callback = Some(resolved_callback);
+    }
+
+    fn set_context(&mut self, context: Box<dyn Any>) {
+        self.context.user_context = Some(Arc::from(context));
+    }
+
+    fn context(&self) -> Option<&dyn Any> {
+        self.context.user_context.as_ref().map(|c| c.as_ref())
+    }
+
+    fn resolve(&mut self) -> Result<EventLoop> {
+        debug!("Resolving service: {:?}", self);
+
+        self.poll = Some(Arc::new(
+            unsafe { ManagedAvahiSimplePoll::new() }.ok_or_else(|| {
+                Error::BrowserError("could not initialize AvahiSimplePoll".into())
+            })?,
+        ));
+
+        let poll = self
+            .poll
+            .as_ref()
+            .ok_or(Error::BrowserError("could not get poll as ref".into()))?
+            .clone();
+
+        let client_params = ManagedAvahiClientParams::builder()
+            .poll(poll)
+            .flags(AvahiClientFlags(0))
+            .callback(Some(client_callback))
+            .userdata(self.context.as_raw())
+            .build()
+            .map_err(Error::BrowserError)?;
+
+        self.client = Some(Arc::new(unsafe { ManagedAvahiClient::new(client_params) }?));
+
+        let client = self
+            .client
+            .as_ref()
+            .ok_or(Error::BrowserError("could not get client as ref".into()))?
+            .clone();
+
+        if let Err(e) = unsafe { self.start(client) } {
+            self.context.invoke_callback(Err(e));
+        }
+
+        Ok(EventLoop::new(
+            self.poll
+                .as_ref()
+                .ok_or(Error::BrowserError("could not get poll as ref".into()))?
+                .clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
+        ))
+    }
+
+    fn resolve_many(
+        keys: &[ServiceKey],
+        concurrency: usize,
+        timeout: Duration,
+    ) -> Vec<Result<ServiceDiscovery>> {
+        let mut batch = ResolveBatch::new(keys, concurrency, timeout);
+
+        if let Err(e) = unsafe { resolve_batch(&mut batch) } {
+            batch.fail(&e);
+        }
+
+        batch.into_results()
+    }
+}
+
+impl AvahiMdnsResolver {
+    /// Starts resolving the service on the specified client, whose poll is iterated by the
+    /// caller.
+    unsafe fn start(&mut self, client: Arc<ManagedAvahiClient>) -> Result<()> {
+        self.context.client = Some(client);
+        create_resolver(&mut self.context)
+    }
+}
+
+/// Runs the resolutions of `batch` on a single client, iterating its poll until the next
+/// resolution times out or every key has completed.
+unsafe fn resolve_batch(batch: &mut ResolveBatch<AvahiMdnsResolver>) -> Result<()> {
+    let poll = Arc::new(
+        ManagedAvahiSimplePoll::new()
+            .ok_or_else(|| Error::BrowserError("could not initialize AvahiSimplePoll".into()))?,
+    );
+
+    let mut context = Box::new(ResolveBatchContext::default());
+
+    let client = Arc::new(ManagedAvahiClient::new(
+        ManagedAvahiClientParams::builder()
+            .poll(poll.clone())
+            .flags(AvahiClientFlags(0))
+            .callback(Some(batch_client_callback))
+            .userdata(context.as_raw())
+            .build()
+            .map_err(Error::BrowserError)?,
+    )?);
+
+    let result = run_batch(batch, &poll, &client, &context);
+
+    // the running resolvers must not outlive the client's callback context
+    if let Err(e) = &result {
+        batch.fail(e);
+    }
+
+    result
+}
+
+unsafe fn run_batch(
+    batch: &mut ResolveBatch<AvahiMdnsResolver>,
+    poll: &ManagedAvahiSimplePoll,
+    client: &Arc<ManagedAvahiClient>,
+    context: &ResolveBatchContext,
+) -> Result<()> {
+    loop {
+        batch.start_queued(|resolver| resolver.start(client.clone()));
+
+        let timeout = match batch.next_timeout() {
+            Some(timeout) => timeout,
+            None => return Ok(()),
+        };
+
+        poll.iterate(timeout)?;
+        context.termination.check()?;
+        batch.collect();
+    }
+}
+
+#[derive(Default, FromRaw, AsRaw)]
+struct ResolveBatchContext {
+    termination: Termination,
+}
+
+unsafe extern "C" fn batch_client_callback(
+    client: *mut AvahiClient,
+    state: AvahiClientState,
+    userdata: *mut c_void,
+) {
+    let context = ResolveBatchContext::from_raw(userdata);
+
+    if state == avahi_sys::AvahiClientState_AVAHI_CLIENT_FAILURE {
+        context
+            .termination
+            .terminate(avahi_util::get_last_error(client).to_string());
+    }
+}
+
+#[derive(FromRaw, AsRaw)]
+struct AvahiResolverContext {
+    client: Option<Arc<ManagedAvahiClient>>,
+    resolver: Option<ManagedAvahiServiceResolver>,
+    name: CString,
+    kind: CString,
+    domain: Option<CString>,
+    interface_index: AvahiIfIndex,
+    resolved_callback: Option<Box<ServiceResolvedCallback>>,
+    user_context: Option<Arc<dyn Any>>,
+    termination: Termination,
+    dispatcher: Dispatcher,
+}
+
+impl AvahiResolverContext {
+    fn new(name: CString, kind: CString) -> Self {
+        Self {
+            client: None,
+            resolver: None,
+            name,
+            kind,
+            domain: None,
+            interface_index: avahi_sys::AVAHI_IF_UNSPEC,
+            resolved_callback: None,
+            user_context: None,
+            termination: Termination::default(),
+            dispatcher: Dispatcher::default(),
+        }
+    }
+
+    fn invoke_callback(&self, result: Result<ServiceDiscovery>) {
+        if let Some(f) = &self.resolved_callback {
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
+        } else {
+            warn!("attempted to invoke resolver callback but none was set");
+        }
+    }
+
+    fn terminate(&self, error: Error) {
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Err(error));
+    }
+}
+
+impl fmt::Debug for AvahiResolverContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AvahiResolverContext")
+            .field("name", &self.name)
+            .field("kind", &self.kind)
+            .field("domain", &self.domain)
+            .field("interface_index", &self.interface_index)
+            .finish()
+    }
+}
+
+unsafe extern "C" fn client_callback(
+    client: *mut AvahiClient,
+    state: AvahiClientState,
+    userdata: *mut c_void,
+) {
+    let context = AvahiResolverContext::from_raw(userdata);
+
+    if state == avahi_sys::AvahiClientState_AVAHI_CLIENT_FAILURE {
+        context.terminate(avahi_util::get_last_error(client));
+    }
+}
+
+unsafe fn create_resolver(context: &mut AvahiResolverContext) -> Result<()> {
+    let raw_context = context.as_raw();
+
+    let client = context
+        .client
+        .as_ref()
+        .ok_or(Error::BrowserError("expected initialized client".into()))?;
+
+    context.resolver = Some(ManagedAvahiServiceResolver::new(
+        ManagedAvahiServiceResolverParams::builder()
+            .client(client.clone())
+            .interface(context.interface_index)
+            .protocol(avahi_sys::AVAHI_PROTO_UNSPEC)
+            .name(context.name.as_ptr())
+            .kind(context.kind.as_ptr())
+            .domain(context.domain.as_ref().map(|d| d.as_ptr()).unwrap_or_null())
+            .aprotocol(avahi_sys::AVAHI_PROTO_UNSPEC)
+            .flags(0)
+            .callback(Some(resolve_callback))
+            .userdata(raw_context)
+            .build()
+            .map_err(Error::BrowserError)?,
+    )?);
+
+    Ok(())
+}
+
+unsafe extern "C" fn resolve_callback(
+    _resolver: *mut AvahiServiceResolver,
+    interface: AvahiIfIndex,
+    protocol: AvahiProtocol,
+    event: AvahiResolverEvent,
+    name: *const c_char,
+    kind: *const c_char,
+    domain: *const c_char,
+    host_name: *const c_char,
+    addr: *const AvahiAddress,
+    port: u16,
+    txt: *mut AvahiStringList,
+    flags: AvahiLookupResultFlags,
+    userdata: *mut c_void,
+) {
+    let context = AvahiResolverContext::from_raw(userdata);
+
+    match event {
+        avahi_sys::AvahiResolverEvent_AVAHI_RESOLVER_FAILURE => {
+            context.terminate(Error::BrowserError(format!(
+                "failed to resolve service `{}` of type `{}`",
+                c_str::to_str(&context.name),
+                c_str::to_str(&context.kind)
+            )));
+        }
+        avahi_sys::AvahiResolverEvent_AVAHI_RESOLVER_FOUND => {
+            let result = resolved_discovery(
+                interface,
+                protocol,
+                c_str::raw_to_str(host_name),
+                addr,
+                c_str::raw_to_str(name),
+                c_str::raw_to_str(kind),
+                c_str::raw_to_str(domain),
+                port,
+                txt,
+                avahi_util::is_local_result(flags),
+            );
+
+            debug!("Service resolved: {:?}", result);
+
+            context.invoke_callback(result);
+        }
+        _ => {}
+    };
+}
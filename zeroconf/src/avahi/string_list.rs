@@ -5,7 +5,7 @@ use avahi_sys::{
     avahi_free, avahi_string_list_add_pair, avahi_string_list_copy, avahi_string_list_equal,
     avahi_string_list_find, avahi_string_list_free, avahi_string_list_get_next,
     avahi_string_list_get_pair, avahi_string_list_length, avahi_string_list_new,
-    avahi_string_list_to_string, AvahiStringList,
+    avahi_string_list_serialize, avahi_string_list_to_string, AvahiStringList,
 };
 use libc::{c_char, c_void};
 use std::marker::PhantomData;
@@ -74,6 +74,25 @@ impl ManagedAvahiStringList {
         avahi_string_list_to_string(self.0).into()
     }
 
+    /// Delegate function for [`avahi_string_list_serialize()`]. Returns the list encoded as TXT
+    /// record data, in the order it is published.
+    ///
+    /// [`avahi_string_list_serialize()`]: https://avahi.org/doxygen/html/strlst_8h.html
+    ///
+    /// # Safety
+    /// This function is unsafe because of the calls to `avahi_string_list_serialize()`.
+    pub unsafe fn serialize(&self) -> Vec<u8> {
+        // with no buffer, `avahi_string_list_serialize()` returns the size required
+        let size = avahi_string_list_serialize(self.0, ptr::null_mut(), 0);
+        let mut data = vec![0u8; size];
+
+        let written =
+            avahi_string_list_serialize(self.0, data.as_mut_ptr() as *mut c_void, data.len());
+
+        data.truncate(written);
+        data
+    }
+
     /// Returns the first node in the list.
     pub fn head(&mut self) -> AvahiStringListNode {
         AvahiStringListNode::new(self.0)
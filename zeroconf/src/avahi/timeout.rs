@@ -0,0 +1,88 @@
+//! Rust friendly `AvahiTimeout` wrappers/helpers
+
+use super::poll::ManagedAvahiSimplePoll;
+use crate::{Error, Result};
+use avahi_sys::{avahi_simple_poll_get, timeval, AvahiTimeout, AvahiTimeoutCallback};
+use libc::c_void;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Wraps an `AvahiTimeout` registered with the `AvahiPoll` API of an `AvahiSimplePoll`.
+///
+/// The callback is invoked once, from `avahi_simple_poll_iterate()`, after the timeout has
+/// elapsed. The timeout is freed on `trait Drop`, cancelling it if it has not fired yet.
+#[derive(Debug)]
+pub struct ManagedAvahiTimeout {
+    pub(crate) inner: *mut AvahiTimeout,
+    free: unsafe extern "C" fn(*mut AvahiTimeout),
+    _poll: Arc<ManagedAvahiSimplePoll>,
+}
+
+impl ManagedAvahiTimeout {
+    /// Registers a new timeout firing after `timeout` has elapsed and verifies it was created;
+    /// returning `Err(String)` if unsuccessful.
+    ///
+    /// # Safety
+    /// This function is unsafe because of the raw pointer dereference.
+    pub unsafe fn new(
+        ManagedAvahiTimeoutParams {
+            poll,
+            timeout,
+            callback,
+            userdata,
+        }: ManagedAvahiTimeoutParams,
+    ) -> Result<Self> {
+        let api = avahi_simple_poll_get(poll.inner());
+
+        let (timeout_new, free) = match ((*api).timeout_new, (*api).timeout_free) {
+            (Some(timeout_new), Some(free)) => (timeout_new, free),
+            _ => {
+                return Err(Error::BrowserError(
+                    "AvahiPoll does not support timeouts".into(),
+                ))
+            }
+        };
+
+        // `AvahiSimplePoll` compares timeouts against `gettimeofday()`
+        let deadline = (SystemTime::now() + timeout)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+
+        let tv = timeval {
+            tv_sec: deadline.as_secs() as _,
+            tv_usec: deadline.subsec_micros() as _,
+        };
+
+        let inner = timeout_new(api, &tv, callback, userdata);
+
+        if inner.is_null() {
+            Err(Error::BrowserError("could not create AvahiTimeout".into()))
+        } else {
+            Ok(Self {
+                inner,
+                free,
+                _poll: poll,
+            })
+        }
+    }
+}
+
+impl Drop for ManagedAvahiTimeout {
+    fn drop(&mut self) {
+        unsafe { (self.free)(self.inner) };
+    }
+}
+
+/// Holds parameters for initializing a new `ManagedAvahiTimeout` with
+/// `ManagedAvahiTimeout::new()`.
+///
+/// See [`AvahiPoll`] for more information about these parameters.
+///
+/// [`AvahiPoll`]: https://avahi.org/doxygen/html/structAvahiPoll.html
+#[derive(Builder, BuilderDelegate)]
+pub struct ManagedAvahiTimeoutParams {
+    poll: Arc<ManagedAvahiSimplePoll>,
+    timeout: Duration,
+    callback: AvahiTimeoutCallback,
+    userdata: *mut c_void,
+}
@@ -1,7 +1,7 @@
 //! Avahi implementation for cross-platform TXT record.
 
 use super::string_list::{AvahiStringListNode, ManagedAvahiStringList};
-use crate::txt_record::TTxtRecord;
+use crate::txt_record::{self, TTxtRecord};
 use crate::Result;
 use libc::c_char;
 use std::cell::UnsafeCell;
@@ -39,23 +39,15 @@ impl TTxtRecord for AvahiTxtRecord {
     }
 
     fn remove(&mut self, key: &str) -> Option<String> {
-        let mut list = unsafe { ManagedAvahiStringList::new() };
-        let mut map = self.to_map();
-        let prev = map.remove(key);
-
-        for (key, value) in map {
-            let c_key = c_string!(key);
-            let c_value = c_string!(value);
+        let prev = self.get(key);
 
-            unsafe {
-                list.add_pair(
-                    c_key.as_ptr() as *const c_char,
-                    c_value.as_ptr() as *const c_char,
-                );
-            }
-        }
+        let entries = self
+            .published_entries()
+            .into_iter()
+            .filter(|(k, _)| k != key)
+            .collect::<Vec<_>>();
 
-        self.0 = UnsafeCell::new(list);
+        *self = Self::from_entries(&entries);
 
         prev
     }
@@ -84,9 +76,52 @@ impl TTxtRecord for AvahiTxtRecord {
     fn values<'a>(&'a self) -> Box<dyn Iterator<Item = String> + 'a> {
         Box::new(Values(Iter::new(self.inner_mut().head())))
     }
+
+    fn with_order(self, keys: &[&str]) -> Self {
+        Self::from_entries(&txt_record::ordered_entries(self.published_entries(), keys))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        unsafe { self.inner().serialize() }
+    }
 }
 
 impl AvahiTxtRecord {
+    fn from_entries(entries: &[(String, String)]) -> Self {
+        let mut list = unsafe { ManagedAvahiStringList::new() };
+
+        // Avahi prepends new pairs to the list and reverses it when serializing, so inserting in
+        // order publishes the entries in order
+        for (key, value) in entries {
+            let c_key = c_string!(key.as_str());
+            let c_value = c_string!(value.as_str());
+
+            unsafe {
+                list.add_pair(
+                    c_key.as_ptr() as *const c_char,
+                    c_value.as_ptr() as *const c_char,
+                );
+            }
+        }
+
+        Self::from(list)
+    }
+
+    /// Returns the entries in the order they are published, which is the reverse of the order of
+    /// the list. Only the most recently inserted value of a duplicated key is kept.
+    fn published_entries(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<(String, String)> = Vec::new();
+
+        for (key, value) in self.iter() {
+            if !entries.iter().any(|(k, _)| *k == key) {
+                entries.push((key, value));
+            }
+        }
+
+        entries.reverse();
+        entries
+    }
+
     #[allow(clippy::mut_from_ref)]
     fn inner_mut(&self) -> &mut ManagedAvahiStringList {
         unsafe { &mut *self.0.get() }
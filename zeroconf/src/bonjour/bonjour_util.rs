@@ -3,10 +3,19 @@
 use std::{ffi::CString, str::FromStr};
 
 use super::constants;
+use crate::ffi::c_str;
 use crate::{
     check_valid_characters, lstrip_underscore, Error, NetworkInterface, Result, ServiceType,
 };
-use bonjour_sys::DNSServiceErrorType;
+#[cfg(target_vendor = "pc")]
+use bonjour_sys::sockaddr_in;
+use bonjour_sys::{sockaddr, DNSServiceErrorType};
+use libc::c_char;
+#[cfg(target_vendor = "apple")]
+use libc::sockaddr_in;
+#[cfg(target_vendor = "apple")]
+use std::ffi::CStr;
+use std::net::IpAddr;
 
 /// Normalizes the specified domain `&str` to conform to a standard enforced by this crate.
 ///
@@ -43,6 +52,44 @@ pub fn interface_from_index(index: u32) -> NetworkInterface {
     }
 }
 
+/// Returns true if the specified SRV target host name refers to the machine this process is
+/// running on.
+///
+/// Bonjour does not flag results that originate from the local host, so the first label of
+/// `host_name` is compared against the machine's host name instead. This is a heuristic: a host
+/// name that was customized at registration or renamed by Bonjour after a conflict is not
+/// recognized.
+pub fn is_local_host_name(host_name: &str) -> bool {
+    local_host_name()
+        .map(|local| same_host_label(host_name, &local))
+        .unwrap_or(false)
+}
+
+fn same_host_label(a: &str, b: &str) -> bool {
+    let label = |s: &str| s.split('.').next().unwrap_or_default().to_lowercase();
+    let a = label(a);
+    !a.is_empty() && a == label(b)
+}
+
+#[cfg(target_vendor = "apple")]
+fn local_host_name() -> Option<String> {
+    let mut buf = [0 as c_char; 256];
+
+    if unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) } != 0 {
+        return None;
+    }
+
+    unsafe { CStr::from_ptr(buf.as_ptr()) }
+        .to_str()
+        .ok()
+        .map(String::from)
+}
+
+#[cfg(target_vendor = "pc")]
+fn local_host_name() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
 /// Executes the specified closure and returns a formatted `Result`
 pub fn sys_exec<F: FnOnce() -> DNSServiceErrorType>(func: F, message: &str) -> Result<()> {
     let err = func();
@@ -57,6 +104,185 @@ pub fn sys_exec<F: FnOnce() -> DNSServiceErrorType>(func: F, message: &str) -> R
     }
 }
 
+/// Returns the version of the running mDNSResponder daemon.
+///
+/// The version is encoded as reported by `DNSServiceGetProperty()`, which allows it to be compared
+/// numerically to determine whether the installed daemon predates a feature.
+pub fn daemon_version() -> Result<u32> {
+    let mut version: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+
+    sys_exec(
+        || unsafe {
+            bonjour_sys::DNSServiceGetProperty(
+                bonjour_sys::kDNSServiceProperty_DaemonVersion.as_ptr() as *const c_char,
+                &mut version as *mut u32 as *mut libc::c_void,
+                &mut size,
+            )
+        },
+        "could not get daemon version",
+    )?;
+
+    Ok(version)
+}
+
+/// Returns the full DNS name of the service instance with the specified name, regtype and domain.
+///
+/// # Safety
+/// This function is unsafe because of the call to `DNSServiceConstructFullName`.
+pub unsafe fn construct_full_name(
+    name: *const c_char,
+    regtype: *const c_char,
+    domain: *const c_char,
+) -> Result<String> {
+    let mut full_name = vec![0 as c_char; constants::BONJOUR_MAX_DOMAIN_NAME];
+
+    sys_exec(
+        || bonjour_sys::DNSServiceConstructFullName(full_name.as_mut_ptr(), name, regtype, domain),
+        "could not construct full name",
+    )?;
+
+    Ok(normalize_domain(c_str::raw_to_str(full_name.as_ptr())))
+}
+
+/// Returns the IPv4 address held by the specified `sockaddr`, as reported by
+/// `DNSServiceGetAddrInfo()`, as a `String`.
+///
+/// # Safety
+/// This function is unsafe because of the raw pointer dereference.
+pub unsafe fn address_to_string(address: *const sockaddr) -> String {
+    let address = address as *const sockaddr_in;
+    assert_not_null!(address);
+
+    // on macOS the bytes are swapped for the ip
+    #[cfg(target_vendor = "apple")]
+    let s_addr = (*address).sin_addr.s_addr.to_le_bytes();
+
+    #[cfg(target_vendor = "pc")]
+    let s_addr = {
+        let s_un = (*address).sin_addr.S_un.S_un_b;
+        [s_un.s_b1, s_un.s_b2, s_un.s_b3, s_un.s_b4]
+    };
+
+    IpAddr::from(s_addr).to_string()
+}
+
+/// Returns the IP address of the specified IPv4 or IPv6 `sockaddr`, or `None` if it is of another
+/// family.
+///
+/// # Safety
+/// This function is unsafe because of the raw pointer dereference.
+#[cfg(target_vendor = "apple")]
+pub unsafe fn sockaddr_to_ip(address: *const sockaddr) -> Option<IpAddr> {
+    assert_not_null!(address);
+
+    match (*(address as *const libc::sockaddr)).sa_family as libc::c_int {
+        libc::AF_INET => address_to_string(address).parse().ok(),
+        libc::AF_INET6 => {
+            let address = address as *const libc::sockaddr_in6;
+            Some(IpAddr::from((*address).sin6_addr.s6_addr))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the IP address of the specified IPv4 or IPv6 `sockaddr`, or `None` if it is of another
+/// family.
+///
+/// # Safety
+/// This function is unsafe because of the raw pointer dereference.
+#[cfg(target_vendor = "pc")]
+pub unsafe fn sockaddr_to_ip(address: *const sockaddr) -> Option<IpAddr> {
+    use windows_sys::Win32::Networking::WinSock::{AF_INET, AF_INET6, SOCKADDR, SOCKADDR_IN6};
+
+    assert_not_null!(address);
+
+    match (*(address as *const SOCKADDR)).sa_family {
+        AF_INET => address_to_string(address).parse().ok(),
+        AF_INET6 => {
+            let address = address as *const SOCKADDR_IN6;
+            Some(IpAddr::from((*address).sin6_addr.u.Byte))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the interface index and friendly name (e.g. "Ethernet 2") of every network adapter, as
+/// reported by `GetAdaptersAddresses()`.
+#[cfg(target_vendor = "pc")]
+pub fn adapter_friendly_names() -> Result<Vec<(u32, String)>> {
+    use windows_sys::Win32::Foundation::{ERROR_BUFFER_OVERFLOW, NO_ERROR};
+    use windows_sys::Win32::NetworkManagement::IpHelper::{
+        GetAdaptersAddresses, GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER,
+        GAA_FLAG_SKIP_MULTICAST, GAA_FLAG_SKIP_UNICAST, IP_ADAPTER_ADDRESSES_LH,
+    };
+    use windows_sys::Win32::Networking::WinSock::AF_UNSPEC;
+
+    let flags = GAA_FLAG_SKIP_UNICAST
+        | GAA_FLAG_SKIP_ANYCAST
+        | GAA_FLAG_SKIP_MULTICAST
+        | GAA_FLAG_SKIP_DNS_SERVER;
+
+    // the initial buffer size recommended by the `GetAdaptersAddresses()` documentation
+    let mut size: u32 = 15 * 1024;
+
+    loop {
+        // `u64` elements keep the buffer aligned for `IP_ADAPTER_ADDRESSES_LH`
+        let mut buffer = vec![0u64; size as usize / 8 + 1];
+        let first = buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH;
+
+        let result = unsafe {
+            GetAdaptersAddresses(AF_UNSPEC as u32, flags, std::ptr::null(), first, &mut size)
+        };
+
+        match result {
+            NO_ERROR => {}
+            // the adapters changed between calls, retry with the size that was reported
+            ERROR_BUFFER_OVERFLOW => continue,
+            code => {
+                return Err(Error::SystemError {
+                    code: code as i32,
+                    message: "GetAdaptersAddresses() reported error".into(),
+                })
+            }
+        }
+
+        let mut names = Vec::new();
+        let mut adapter = first as *const IP_ADAPTER_ADDRESSES_LH;
+
+        while !adapter.is_null() {
+            let adapter_ref = unsafe { &*adapter };
+
+            // the IPv4 index is zero if IPv4 is disabled on the adapter
+            let index = match unsafe { adapter_ref.Anonymous1.Anonymous.IfIndex } {
+                0 => adapter_ref.Ipv6IfIndex,
+                index => index,
+            };
+
+            names.push((index, unsafe { wide_to_string(adapter_ref.FriendlyName) }));
+            adapter = adapter_ref.Next;
+        }
+
+        return Ok(names);
+    }
+}
+
+/// Copies the specified null-terminated UTF-16 string into a `String`.
+#[cfg(target_vendor = "pc")]
+unsafe fn wide_to_string(wide: *const u16) -> String {
+    if wide.is_null() {
+        return String::new();
+    }
+
+    let mut len = 0;
+
+    while *wide.add(len) != 0 {
+        len += 1;
+    }
+
+    String::from_utf16_lossy(std::slice::from_raw_parts(wide, len))
+}
+
 /// Formats the specified `ServiceType` as a `CString` for use with Bonjour
 pub fn format_regtype(service_type: &ServiceType) -> CString {
     let mut regtype = vec![format!(
@@ -158,6 +384,18 @@ mod tests {
         assert_eq!(interface_index(NetworkInterface::AtIndex(42)), 42);
     }
 
+    #[test]
+    fn same_host_label_ignores_domain_and_case() {
+        assert!(same_host_label("MyMac.local.", "mymac"));
+        assert!(same_host_label("mymac.local", "MyMac.local"));
+    }
+
+    #[test]
+    fn same_host_label_returns_false_for_other_host() {
+        assert!(!same_host_label("other-mac.local.", "mymac"));
+        assert!(!same_host_label("", ""));
+    }
+
     #[test]
     fn normalize_domain_removes_trailing_dot() {
         assert_eq!(
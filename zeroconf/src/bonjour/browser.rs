@@ -5,27 +5,29 @@ use super::service_ref::{
 };
 use super::txt_record_ref::ManagedTXTRecordRef;
 use super::{bonjour_util, constants};
-use crate::ffi::{c_str, AsRaw, FromRaw};
+use crate::browser::DuplicateNameDetector;
+use crate::event_loop::{Dispatcher, Termination};
+use crate::ffi::{self, c_str, AsRaw, FromRaw, UnwrapOrNull};
 use crate::{prelude::*, Error};
-use crate::{BrowserEvent, ServiceBrowserCallback, ServiceDiscovery, ServiceRemoval};
+use crate::{
+    BrowserEvent, ServiceAddress, ServiceBrowserCallback, ServiceDiscovery, ServiceInstance,
+    ServiceRemoval,
+};
 use crate::{EventLoop, NetworkInterface, Result, ServiceType, TxtRecord};
-#[cfg(target_vendor = "pc")]
-use bonjour_sys::sockaddr_in;
 use bonjour_sys::{DNSServiceErrorType, DNSServiceFlags, DNSServiceRef};
-#[cfg(target_vendor = "apple")]
-use libc::sockaddr_in;
 use libc::{c_char, c_uchar, c_void};
 use std::any::Any;
 use std::ffi::CString;
 use std::fmt::{self, Formatter};
-use std::net::IpAddr;
-use std::ptr;
+use std::mem;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub struct BonjourMdnsBrowser {
     service: Arc<Mutex<ManagedDNSServiceRef>>,
     kind: CString,
+    domain: Option<CString>,
     interface_index: u32,
     context: Box<BonjourBrowserContext>,
 }
@@ -35,8 +37,12 @@ impl TMdnsBrowser for BonjourMdnsBrowser {
         Self {
             service: Arc::default(),
             kind: bonjour_util::format_regtype(&service_type),
+            domain: None,
             interface_index: constants::BONJOUR_IF_UNSPEC,
-            context: Box::default(),
+            context: Box::new(BonjourBrowserContext {
+                auto_resolve: true,
+                ..Default::default()
+            }),
         }
     }
 
@@ -48,6 +54,30 @@ impl TMdnsBrowser for BonjourMdnsBrowser {
         bonjour_util::interface_from_index(self.interface_index)
     }
 
+    fn set_domain(&mut self, domain: &str) {
+        self.domain = Some(c_string!(domain));
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.domain.as_ref().map(c_str::to_str)
+    }
+
+    fn set_auto_resolve(&mut self, auto_resolve: bool) {
+        self.context.auto_resolve = auto_resolve;
+    }
+
+    fn auto_resolve(&self) -> bool {
+        self.context.auto_resolve
+    }
+
+    fn set_resolve_timeout(&mut self, timeout: Option<Duration>) {
+        self.context.resolve_timeout = timeout;
+    }
+
+    fn resolve_timeout(&self) -> Option<Duration> {
+        self.context.resolve_timeout
+    }
+
     fn set_service_callback(&mut self, service_discovered_callback: Box<ServiceBrowserCallback>) {
         self.context.service_discovered_callback = Some(service_discovered_callback);
     }
@@ -72,7 +102,7 @@ impl TMdnsBrowser for BonjourMdnsBrowser {
             .flags(0)
             .interface_index(self.interface_index)
             .regtype(self.kind.as_ptr())
-            .domain(ptr::null_mut())
+            .domain(self.domain.as_ref().map(|d| d.as_ptr()).unwrap_or_null())
             .callback(Some(browse_callback))
             .context(self.context.as_raw())
             .build()
@@ -80,7 +110,11 @@ impl TMdnsBrowser for BonjourMdnsBrowser {
 
         unsafe { service_lock.browse_services(browse_params)? };
 
-        Ok(EventLoop::new(self.service.clone()))
+        Ok(EventLoop::new(
+            self.service.clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
+        ))
     }
 }
 
@@ -92,17 +126,138 @@ struct BonjourBrowserContext {
     resolved_domain: Option<String>,
     resolved_port: u16,
     resolved_txt: Option<TxtRecord>,
+    resolved_host_name: Option<String>,
+    resolved_addresses: Vec<ServiceAddress>,
+    resolved_ttl: Option<u32>,
+    resolve_deadline: Option<Instant>,
     user_context: Option<Arc<dyn Any>>,
+    auto_resolve: bool,
+    resolve_timeout: Option<Duration>,
+    duplicates: DuplicateNameDetector,
+    termination: Termination,
+    dispatcher: Dispatcher,
 }
 
 impl BonjourBrowserContext {
     fn invoke_callback(&self, result: Result<BrowserEvent>) {
         if let Some(f) = &self.service_discovered_callback {
-            f(result, self.user_context.clone());
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
         } else {
             warn!("attempted to invoke callback but none was set");
         }
     }
+
+    fn invoke_discovered(&mut self, service: ServiceDiscovery) {
+        let duplicate = self.duplicates.observe(&service);
+
+        self.invoke_callback(Ok(BrowserEvent::Add(service)));
+
+        if let Some(duplicate) = duplicate {
+            self.invoke_callback(Ok(BrowserEvent::DuplicateNameDetected(Box::new(duplicate))));
+        }
+    }
+
+    fn invoke_resolve_timed_out(&mut self, interface_index: u32) {
+        if !self.resolved_addresses.is_empty() {
+            // report the addresses that did arrive in time
+            match self.take_discovery() {
+                Ok(service) => self.invoke_discovered(service),
+                Err(e) => self.invoke_callback(Err(e)),
+            }
+            return;
+        }
+
+        self.resolved_txt = None;
+        self.resolved_host_name = None;
+        self.resolved_ttl = None;
+
+        let (name, kind, domain) = match (
+            self.resolved_name.take(),
+            self.resolved_kind.take(),
+            self.resolved_domain.take(),
+        ) {
+            (Some(name), Some(kind), Some(domain)) => (name, kind, domain),
+            _ => return,
+        };
+
+        self.invoke_callback(Ok(BrowserEvent::ResolveTimedOut(service_instance(
+            &name,
+            &kind,
+            &domain,
+            interface_index,
+        ))));
+    }
+
+    /// Builds the `ServiceDiscovery` for the addresses collected so far, resetting the state of
+    /// the current resolution.
+    fn take_discovery(&mut self) -> Result<ServiceDiscovery> {
+        let addresses = mem::take(&mut self.resolved_addresses);
+        let name = self.resolved_name.take();
+        let kind = self.resolved_kind.take();
+        let domain = self.resolved_domain.take();
+        let host_name = self.resolved_host_name.take().unwrap_or_default();
+        let ttl = self.resolved_ttl.take();
+        let txt = self.resolved_txt.take();
+
+        let first = addresses.first().cloned().ok_or(Error::BrowserError(
+            "no address was resolved for BonjourBrowserContext".into(),
+        ))?;
+
+        let domain = bonjour_util::normalize_domain(&domain.ok_or(Error::BrowserError(
+            "could not get domain from BonjourBrowserContext".into(),
+        ))?);
+
+        let kind = bonjour_util::normalize_domain(&kind.ok_or(Error::BrowserError(
+            "could not get kind from BonjourBrowserContext".into(),
+        ))?);
+
+        let name = name.ok_or(Error::BrowserError(
+            "could not get name from BonjourBrowserContext".into(),
+        ))?;
+
+        let local_host = bonjour_util::is_local_host_name(&host_name);
+
+        Ok(ServiceDiscovery::builder()
+            .name(name)
+            .service_type(bonjour_util::parse_regtype(&kind)?)
+            .domain(domain)
+            .host_name(host_name)
+            .address(first.address().to_string())
+            // on macOS the bytes are swapped for the port
+            .port(self.resolved_port.to_be())
+            .txt(txt)
+            .local_host(local_host)
+            .interface(*first.interface())
+            .ttl(ttl.map(|ttl| Duration::from_secs(ttl.into())))
+            .addresses(addresses)
+            .build()
+            .expect("could not build ServiceResolution"))
+    }
+
+    fn reset_resolved(&mut self) {
+        self.resolved_name = None;
+        self.resolved_kind = None;
+        self.resolved_domain = None;
+        self.resolved_txt = None;
+        self.resolved_host_name = None;
+        self.resolved_addresses.clear();
+        self.resolved_ttl = None;
+    }
+
+    fn terminate(&self, error: Error) {
+        let event = match &error {
+            Error::MdnsSystemError { code, .. }
+                if *code == constants::BONJOUR_ERR_SERVICE_NOT_RUNNING =>
+            {
+                BrowserEvent::Stopped
+            }
+            e => BrowserEvent::Failed(e.to_string()),
+        };
+
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Ok(event));
+    }
 }
 
 impl fmt::Debug for BonjourBrowserContext {
@@ -128,28 +283,26 @@ unsafe extern "system" fn browse_callback(
 ) {
     let ctx = BonjourBrowserContext::from_raw(context);
 
-    //if error != 0 {
-    //    ctx.invoke_callback(Err(format!(
-    //        "browse_callback() reported error (code: {})",
-    //        error
-    //    )
-    //    .into()));
-    //    return;
-    //}
     if error != 0 {
-        ctx.invoke_callback(Err(Error::MdnsSystemError {
+        ctx.terminate(Error::MdnsSystemError {
             code: error,
             message: "browse_callback() reported error".into(),
-        }));
+        });
         return;
     }
 
-    if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 {
+    if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 && !ctx.auto_resolve {
+        handle_browse_found(ctx, name, regtype, domain, interface_index);
+    } else if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 {
         if let Err(e) = handle_browse_add(ctx, name, regtype, domain, interface_index) {
             ctx.invoke_callback(Err(e));
         }
     } else {
-        handle_browse_remove(ctx, name, regtype, domain);
+        handle_browse_remove(ctx, name, regtype, domain, interface_index);
+    }
+
+    if flags & bonjour_sys::kDNSServiceFlagsMoreComing == 0 {
+        ctx.invoke_callback(Ok(BrowserEvent::AllForNow));
     }
 }
 
@@ -163,8 +316,11 @@ unsafe fn handle_browse_add(
     ctx.resolved_name = Some(c_str::copy_raw(name));
     ctx.resolved_kind = Some(c_str::copy_raw(regtype));
     ctx.resolved_domain = Some(c_str::copy_raw(domain));
+    ctx.resolve_deadline = ctx.resolve_timeout.map(|timeout| Instant::now() + timeout);
+
+    let mut service = ManagedDNSServiceRef::default();
 
-    ManagedDNSServiceRef::default().resolve_service(
+    service.start_resolve_service(
         ServiceResolveParams::builder()
             .flags(bonjour_sys::kDNSServiceFlagsForceMulticast)
             .interface_index(interface_index)
@@ -175,7 +331,47 @@ unsafe fn handle_browse_add(
             .context(ctx.as_raw())
             .build()
             .map_err(Error::BrowserError)?,
-    )
+    )?;
+
+    if !process_result_before(&service, ctx.resolve_deadline)? {
+        ctx.invoke_resolve_timed_out(interface_index);
+    }
+
+    Ok(())
+}
+
+/// Processes the first result of the specified service, returning false if none arrived before
+/// `deadline`. Without a deadline, this blocks until a result arrives.
+unsafe fn process_result_before(
+    service: &ManagedDNSServiceRef,
+    deadline: Option<Instant>,
+) -> Result<bool> {
+    if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        if ffi::bonjour::read_select(service.sock_fd(), remaining)? == 0 {
+            return Ok(false);
+        }
+    }
+
+    service.process_result()?;
+
+    Ok(true)
+}
+
+unsafe fn handle_browse_found(
+    ctx: &mut BonjourBrowserContext,
+    name: *const c_char,
+    regtype: *const c_char,
+    domain: *const c_char,
+    interface_index: u32,
+) {
+    ctx.invoke_callback(Ok(BrowserEvent::Found(service_instance(
+        c_str::raw_to_str(name),
+        c_str::raw_to_str(regtype),
+        c_str::raw_to_str(domain),
+        interface_index,
+    ))));
 }
 
 unsafe fn handle_browse_remove(
@@ -183,23 +379,59 @@ unsafe fn handle_browse_remove(
     name: *const c_char,
     regtype: *const c_char,
     domain: *const c_char,
+    interface_index: u32,
 ) {
-    let name = c_str::raw_to_str(name);
-    let regtype = c_str::raw_to_str(regtype);
-    let domain = c_str::raw_to_str(domain);
+    let removal = service_removal(
+        c_str::raw_to_str(name),
+        c_str::raw_to_str(regtype),
+        c_str::raw_to_str(domain),
+        interface_index,
+    );
 
+    ctx.duplicates
+        .remove(removal.name(), removal.domain(), *removal.interface());
+
+    ctx.invoke_callback(Ok(BrowserEvent::Remove(removal)));
+}
+
+/// Builds the `ServiceInstance` for a browse result.
+fn service_instance(
+    name: &str,
+    regtype: &str,
+    domain: &str,
+    interface_index: u32,
+) -> ServiceInstance {
     // Remove the "." suffix to be consistent with the Avahi implementation.
-    let regtype = regtype.strip_suffix(".").unwrap_or(domain);
-    let domain = domain.strip_suffix(".").unwrap_or(domain);
-
-    ctx.invoke_callback(Ok(BrowserEvent::Remove(
-        ServiceRemoval::builder()
-            .name(name.to_string())
-            .kind(regtype.to_string())
-            .domain(domain.to_string())
-            .build()
-            .expect("could not build ServiceRemoval"),
-    )));
+    let regtype = regtype.strip_suffix('.').unwrap_or(regtype);
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+
+    ServiceInstance::builder()
+        .name(name.to_string())
+        .kind(regtype.to_string())
+        .domain(domain.to_string())
+        .interface(bonjour_util::interface_from_index(interface_index))
+        .build()
+        .expect("could not build ServiceInstance")
+}
+
+/// Builds the `ServiceRemoval` for a browse result that is no longer available.
+fn service_removal(
+    name: &str,
+    regtype: &str,
+    domain: &str,
+    interface_index: u32,
+) -> ServiceRemoval {
+    // Remove the "." suffix to be consistent with the Avahi implementation.
+    let regtype = regtype.strip_suffix('.').unwrap_or(regtype);
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+
+    ServiceRemoval::builder()
+        .name(name.to_string())
+        .kind(regtype.to_string())
+        .domain(domain.to_string())
+        .interface(bonjour_util::interface_from_index(interface_index))
+        .build()
+        .expect("could not build ServiceRemoval")
 }
 
 unsafe extern "system" fn resolve_callback(
@@ -257,7 +489,9 @@ unsafe fn handle_resolve(
         None
     };
 
-    ManagedDNSServiceRef::default().get_address_info(
+    let mut service = ManagedDNSServiceRef::default();
+
+    service.start_get_address_info(
         GetAddressInfoParams::builder()
             .flags(bonjour_sys::kDNSServiceFlagsForceMulticast)
             .interface_index(interface_index)
@@ -267,90 +501,120 @@ unsafe fn handle_resolve(
             .context(ctx.as_raw())
             .build()
             .map_err(Error::BrowserError)?,
-    )
+    )?;
+
+    // collect every address of the host, until Bonjour reports that no more are coming
+    loop {
+        if ctx.resolved_name.is_none() {
+            break;
+        }
+
+        if !process_result_before(&service, ctx.resolve_deadline)? {
+            ctx.invoke_resolve_timed_out(interface_index);
+            break;
+        }
+    }
+
+    Ok(())
 }
 
 unsafe extern "system" fn get_address_info_callback(
     _sd_ref: DNSServiceRef,
-    _flags: DNSServiceFlags,
-    _interface_index: u32,
+    flags: DNSServiceFlags,
+    interface_index: u32,
     error: DNSServiceErrorType,
     hostname: *const c_char,
     address: *const bonjour_sys::sockaddr,
-    _ttl: u32,
+    ttl: u32,
     context: *mut c_void,
 ) {
     let ctx = BonjourBrowserContext::from_raw(context);
-    if let Err(e) = handle_get_address_info(ctx, error, address, hostname) {
+
+    let result =
+        handle_get_address_info(ctx, flags, error, interface_index, address, hostname, ttl);
+
+    if let Err(e) = result {
         ctx.invoke_callback(Err(e));
     }
 }
 
 unsafe fn handle_get_address_info(
     ctx: &mut BonjourBrowserContext,
+    flags: DNSServiceFlags,
     error: DNSServiceErrorType,
+    interface_index: u32,
     address: *const bonjour_sys::sockaddr,
     hostname: *const c_char,
+    ttl: u32,
 ) -> Result<()> {
-    // this callback runs multiple times for some reason
+    // results may still arrive after the service has been reported
     if ctx.resolved_name.is_none() {
         return Ok(());
     }
 
     if error != 0 {
+        ctx.reset_resolved();
+
         return Err(Error::MdnsSystemError {
             code: error,
             message: "get_address_info_callback() reported error".into(),
         });
     }
 
-    // on macOS the bytes are swapped for the port
-    let port: u16 = ctx.resolved_port.to_be();
+    if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 {
+        match bonjour_util::sockaddr_to_ip(address) {
+            Some(address) => ctx.resolved_addresses.push(
+                ServiceAddress::builder()
+                    .address(address)
+                    .interface(bonjour_util::interface_from_index(interface_index))
+                    .build()
+                    .expect("could not build ServiceAddress"),
+            ),
+            None => warn!("ignoring resolved address of unsupported family"),
+        }
 
-    // on macOS the bytes are swapped for the ip
-    #[cfg(target_vendor = "apple")]
-    let ip = {
-        let address = address as *const sockaddr_in;
-        assert_not_null!(address);
-        let s_addr = (*address).sin_addr.s_addr.to_le_bytes();
-        IpAddr::from(s_addr).to_string()
-    };
+        ctx.resolved_host_name = Some(c_str::copy_raw(hostname));
+        ctx.resolved_ttl = Some(ctx.resolved_ttl.map_or(ttl, |t| t.min(ttl)));
+    }
 
-    #[cfg(target_vendor = "pc")]
-    let ip = {
-        let address = address as *const sockaddr_in;
-        assert_not_null!(address);
-        let s_un = (*address).sin_addr.S_un.S_un_b;
-        let s_addr = [s_un.s_b1, s_un.s_b2, s_un.s_b3, s_un.s_b4];
-        IpAddr::from(s_addr).to_string()
-    };
+    if flags & bonjour_sys::kDNSServiceFlagsMoreComing != 0 || ctx.resolved_addresses.is_empty() {
+        return Ok(());
+    }
 
-    let hostname = c_str::copy_raw(hostname);
+    let result = ctx.take_discovery()?;
 
-    let domain = bonjour_util::normalize_domain(&ctx.resolved_domain.take().ok_or(
-        Error::BrowserError("could not get domain from BonjourBrowserContext".into()),
-    )?);
+    ctx.invoke_discovered(result);
 
-    let kind = bonjour_util::normalize_domain(&ctx.resolved_kind.take().ok_or(
-        Error::BrowserError("could not get kind from BonjourBrowserContext".into()),
-    )?);
+    Ok(())
+}
 
-    let name = ctx.resolved_name.take().ok_or(Error::BrowserError(
-        "could not get name from BonjourBrowserContext".into(),
-    ))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let result = ServiceDiscovery::builder()
-        .name(name)
-        .service_type(bonjour_util::parse_regtype(&kind)?)
-        .domain(domain)
-        .host_name(hostname)
-        .address(ip)
-        .port(port)
-        .txt(ctx.resolved_txt.take())
-        .build()
-        .expect("could not build ServiceResolution");
+    #[test]
+    fn service_removal_strips_trailing_dots() {
+        let removal = service_removal("foo", "_http._tcp.", "local.", 2);
 
-    ctx.invoke_callback(Ok(BrowserEvent::Add(result)));
+        assert_eq!(removal.kind(), "_http._tcp");
+        assert_eq!(removal.domain(), "local");
+        assert_eq!(removal.interface(), &NetworkInterface::AtIndex(2));
+    }
 
-    Ok(())
+    #[test]
+    fn service_removal_keeps_regtype_without_trailing_dot() {
+        let removal = service_removal("foo", "_http._tcp", "local.", 2);
+
+        assert_eq!(removal.kind(), "_http._tcp");
+        assert_eq!(removal.domain(), "local");
+    }
+
+    #[test]
+    fn service_instance_keeps_regtype_without_trailing_dot() {
+        let instance = service_instance("foo", "_http._tcp", "local", 1);
+
+        assert_eq!(instance.kind(), "_http._tcp");
+        assert_eq!(instance.domain(), "local");
+        assert_eq!(*instance.interface(), NetworkInterface::AtIndex(1));
+    }
 }
@@ -1,4 +1,8 @@
-use bonjour_sys::DNSServiceFlags;
+use bonjour_sys::{DNSServiceErrorType, DNSServiceFlags};
 
 pub const BONJOUR_IF_UNSPEC: u32 = 0;
+pub const BONJOUR_MAX_DOMAIN_NAME: usize = 1009;
 pub const BONJOUR_RENAME_FLAGS: DNSServiceFlags = 0;
+pub const BONJOUR_NO_RENAME_FLAGS: DNSServiceFlags = bonjour_sys::kDNSServiceFlagsNoAutoRename;
+pub const BONJOUR_ERR_NAME_CONFLICT: DNSServiceErrorType = -65548;
+pub const BONJOUR_ERR_SERVICE_NOT_RUNNING: DNSServiceErrorType = -65563;
@@ -0,0 +1,154 @@
+//! Bonjour implementation for cross-platform domain browser
+
+use super::service_ref::{EnumerateDomainsParams, ManagedDNSServiceRef};
+use super::{bonjour_util, constants};
+use crate::event_loop::{Dispatcher, Termination};
+use crate::ffi::{c_str, AsRaw, FromRaw};
+use crate::{prelude::*, Error};
+use crate::{
+    Domain, DomainBrowserCallback, DomainEvent, DomainKind, EventLoop, NetworkInterface, Result,
+};
+use bonjour_sys::{DNSServiceErrorType, DNSServiceFlags, DNSServiceRef};
+use libc::{c_char, c_void};
+use std::any::Any;
+use std::fmt::{self, Formatter};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+pub struct BonjourMdnsDomainBrowser {
+    service: Arc<Mutex<ManagedDNSServiceRef>>,
+    kind: DomainKind,
+    interface_index: u32,
+    context: Box<BonjourDomainBrowserContext>,
+}
+
+impl TMdnsDomainBrowser for BonjourMdnsDomainBrowser {
+    fn new(kind: DomainKind) -> Self {
+        Self {
+            service: Arc::default(),
+            kind,
+            interface_index: constants::BONJOUR_IF_UNSPEC,
+            context: Box::default(),
+        }
+    }
+
+    fn kind(&self) -> DomainKind {
+        self.kind
+    }
+
+    fn set_network_interface(&mut self, interface: NetworkInterface) {
+        self.interface_index = bonjour_util::interface_index(interface);
+    }
+
+    fn network_interface(&self) -> NetworkInterface {
+        bonjour_util::interface_from_index(self.interface_index)
+    }
+
+    fn set_domain_callback(&mut self, domain_callback: Box<DomainBrowserCallback>) {
+        self.context.domain_callback = Some(domain_callback);
+    }
+
+    fn set_context(&mut self, context: Box<dyn Any>) {
+        self.context.user_context = Some(Arc::from(context));
+    }
+
+    fn context(&self) -> Option<&dyn Any> {
+        self.context.user_context.as_ref().map(|c| c.as_ref())
+    }
+
+    fn browse_domains(&mut self) -> Result<EventLoop> {
+        debug!("Browsing domains: {:?}", self);
+
+        let mut service_lock = self
+            .service
+            .lock()
+            .expect("should have been able to obtain lock on service ref");
+
+        let enumerate_params = EnumerateDomainsParams::builder()
+            .flags(enumerate_flags(self.kind))
+            .interface_index(self.interface_index)
+            .callback(Some(enumerate_callback))
+            .context(self.context.as_raw())
+            .build()
+            .map_err(Error::BrowserError)?;
+
+        unsafe { service_lock.enumerate_domains(enumerate_params)? };
+
+        Ok(EventLoop::new(
+            self.service.clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
+        ))
+    }
+}
+
+#[derive(Default, FromRaw, AsRaw)]
+struct BonjourDomainBrowserContext {
+    domain_callback: Option<Box<DomainBrowserCallback>>,
+    user_context: Option<Arc<dyn Any>>,
+    termination: Termination,
+    dispatcher: Dispatcher,
+}
+
+impl BonjourDomainBrowserContext {
+    fn invoke_callback(&self, result: Result<DomainEvent>) {
+        if let Some(f) = &self.domain_callback {
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
+        } else {
+            warn!("attempted to invoke domain browser callback but none was set");
+        }
+    }
+
+    fn terminate(&self, error: Error) {
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Err(error));
+    }
+}
+
+impl fmt::Debug for BonjourDomainBrowserContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BonjourDomainBrowserContext").finish()
+    }
+}
+
+/// Returns the `DNSServiceEnumerateDomains()` flags that list domains of the specified kind.
+fn enumerate_flags(kind: DomainKind) -> DNSServiceFlags {
+    match kind {
+        DomainKind::Browse => bonjour_sys::kDNSServiceFlagsBrowseDomains,
+        DomainKind::Registration => bonjour_sys::kDNSServiceFlagsRegistrationDomains,
+    }
+}
+
+unsafe extern "system" fn enumerate_callback(
+    _sd_ref: DNSServiceRef,
+    flags: DNSServiceFlags,
+    interface_index: u32,
+    error: DNSServiceErrorType,
+    reply_domain: *const c_char,
+    context: *mut c_void,
+) {
+    let ctx = BonjourDomainBrowserContext::from_raw(context);
+
+    if error != 0 {
+        ctx.terminate(Error::MdnsSystemError {
+            code: error,
+            message: "enumerate_callback() reported error".into(),
+        });
+        return;
+    }
+
+    let domain = Domain::builder()
+        .name(bonjour_util::normalize_domain(c_str::raw_to_str(
+            reply_domain,
+        )))
+        .interface(bonjour_util::interface_from_index(interface_index))
+        .build()
+        .expect("could not build Domain");
+
+    if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 {
+        ctx.invoke_callback(Ok(DomainEvent::DomainAdded(domain)));
+    } else {
+        ctx.invoke_callback(Ok(DomainEvent::DomainRemoved(domain)));
+    }
+}
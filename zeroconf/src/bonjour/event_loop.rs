@@ -1,14 +1,28 @@
 //! Event loop for running a `MdnsService` or `MdnsBrowser`.
 
 use super::service_ref::ManagedDNSServiceRef;
-use crate::event_loop::TEventLoop;
+use crate::event_loop::{Dispatcher, TEventLoop, Termination, Waker};
 use crate::{ffi, Result};
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(new)]
 pub struct BonjourEventLoop {
     service: Arc<Mutex<ManagedDNSServiceRef>>,
+    termination: Termination,
+    dispatcher: Dispatcher,
+    #[new(value = "Instant::now()")]
+    started: Instant,
+}
+
+impl BonjourEventLoop {
+    /// Returns `None`, as a `poll()` of this event loop cannot be interrupted from another thread;
+    /// it returns once the socket is readable or the timeout has elapsed.
+    pub(crate) fn waker(&self) -> Option<Waker> {
+        None
+    }
 }
 
 impl TEventLoop for BonjourEventLoop {
@@ -18,6 +32,8 @@ impl TEventLoop for BonjourEventLoop {
     /// `select()` on the underlying socket with the specified timeout. If the socket contains no
     /// new data, the blocking call is not made.
     fn poll(&self, timeout: Duration) -> Result<()> {
+        self.termination.check()?;
+
         let service = self
             .service
             .lock()
@@ -31,4 +47,34 @@ impl TEventLoop for BonjourEventLoop {
             Ok(())
         }
     }
+
+    fn dispatch_count(&self) -> usize {
+        self.dispatcher.count()
+    }
+
+    fn set_slow_callback_threshold(&self, threshold: Option<Duration>) {
+        self.dispatcher.set_slow_threshold(threshold);
+    }
+
+    fn last_error(&self) -> Option<String> {
+        self.termination.reason()
+    }
+
+    fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Exposes the socket of the underlying `DNSServiceRef`, so it can be registered with an external
+/// reactor (e.g. `epoll` or `kqueue`) and `poll()` only called once it is readable.
+#[cfg(unix)]
+impl AsRawFd for BonjourEventLoop {
+    fn as_raw_fd(&self) -> RawFd {
+        let service = self
+            .service
+            .lock()
+            .expect("should have been able to obtain lock on service ref");
+
+        unsafe { service.sock_fd() }
+    }
 }
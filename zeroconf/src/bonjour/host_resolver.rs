@@ -0,0 +1,116 @@
+//! Bonjour mDNS host name resolution
+
+use super::service_ref::{GetAddressInfoParams, ManagedDNSServiceRef};
+use super::{bonjour_util, constants};
+use crate::ffi::{self, AsRaw, FromRaw};
+use crate::host::OTHER_FAMILY_GRACE;
+use crate::prelude::*;
+use crate::{Error, Result};
+use bonjour_sys::{DNSServiceErrorType, DNSServiceFlags, DNSServiceRef};
+use libc::{c_char, c_void};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+#[derive(Default, FromRaw, AsRaw)]
+struct HostNameResolverContext {
+    addresses: Vec<IpAddr>,
+    done: bool,
+    error: Option<Error>,
+}
+
+/// Resolves the specified host name over mDNS, waiting up to `timeout` for an answer.
+///
+/// IPv4 and IPv6 addresses are requested together. Once addresses of either family have been
+/// resolved, those of the other are waited for only briefly, so that hosts with a single address
+/// family do not wait out the full `timeout`.
+pub fn resolve_host_name(host_name: &str, timeout: Duration) -> Result<Vec<IpAddr>> {
+    let mut deadline = Instant::now() + timeout;
+    let mut context = Box::<HostNameResolverContext>::default();
+    let mut service = ManagedDNSServiceRef::default();
+    let name = c_string!(host_name);
+
+    unsafe {
+        service.start_get_address_info(
+            GetAddressInfoParams::builder()
+                .flags(bonjour_sys::kDNSServiceFlagsForceMulticast)
+                .interface_index(constants::BONJOUR_IF_UNSPEC)
+                .protocol(
+                    bonjour_sys::kDNSServiceProtocol_IPv4 | bonjour_sys::kDNSServiceProtocol_IPv6,
+                )
+                .hostname(name.as_ptr())
+                .callback(Some(get_address_info_callback))
+                .context(context.as_raw())
+                .build()
+                .map_err(Error::BrowserError)?,
+        )?;
+    }
+
+    // `done` is set by `get_address_info_callback()` once no more results are queued
+    loop {
+        let both_families = context.addresses.iter().any(IpAddr::is_ipv4)
+            && context.addresses.iter().any(IpAddr::is_ipv6);
+
+        if context.error.is_some() || (context.done && both_families) {
+            break;
+        }
+
+        let now = Instant::now();
+
+        if !context.addresses.is_empty() {
+            deadline = deadline.min(now + OTHER_FAMILY_GRACE);
+        }
+
+        if now >= deadline {
+            break;
+        }
+
+        if unsafe { ffi::bonjour::read_select(service.sock_fd(), deadline - now)? } > 0 {
+            unsafe { service.process_result()? };
+        }
+    }
+
+    if context.addresses.is_empty() {
+        if let Some(error) = context.error.take() {
+            return Err(error);
+        }
+
+        return Err(Error::HostNotFound(format!(
+            "`{}` was not resolved over mDNS within {:?}",
+            host_name, timeout
+        )));
+    }
+
+    Ok(std::mem::take(&mut context.addresses))
+}
+
+unsafe extern "system" fn get_address_info_callback(
+    _sd_ref: DNSServiceRef,
+    flags: DNSServiceFlags,
+    _interface_index: u32,
+    error: DNSServiceErrorType,
+    _hostname: *const c_char,
+    address: *const bonjour_sys::sockaddr,
+    _ttl: u32,
+    context: *mut c_void,
+) {
+    let ctx = HostNameResolverContext::from_raw(context);
+
+    if error != 0 {
+        ctx.error = Some(Error::MdnsSystemError {
+            code: error,
+            message: "get_address_info_callback() reported error".into(),
+        });
+        ctx.done = true;
+        return;
+    }
+
+    if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 {
+        if let Some(address) = bonjour_util::sockaddr_to_ip(address) {
+            if !ctx.addresses.contains(&address) {
+                ctx.addresses.push(address);
+            }
+        }
+    }
+
+    ctx.done = flags & bonjour_sys::kDNSServiceFlagsMoreComing == 0;
+}
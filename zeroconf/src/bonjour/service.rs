@@ -2,6 +2,7 @@
 
 use super::service_ref::{ManagedDNSServiceRef, RegisterServiceParams};
 use super::{bonjour_util, constants};
+use crate::event_loop::{Dispatcher, Termination};
 use crate::ffi::c_str::{self, AsCChars};
 use crate::ffi::{AsRaw, FromRaw, UnwrapOrNull};
 use crate::{prelude::*, Error};
@@ -21,6 +22,7 @@ pub struct BonjourMdnsService {
     kind: CString,
     port: u16,
     name: Option<CString>,
+    auto_rename: bool,
     domain: Option<CString>,
     host: Option<CString>,
     interface_index: u32,
@@ -35,6 +37,7 @@ impl TMdnsService for BonjourMdnsService {
             kind: bonjour_util::format_regtype(&service_type),
             port,
             name: None,
+            auto_rename: true,
             domain: None,
             host: None,
             interface_index: constants::BONJOUR_IF_UNSPEC,
@@ -53,6 +56,14 @@ impl TMdnsService for BonjourMdnsService {
         self.name.as_ref().map(c_str::to_str)
     }
 
+    fn set_auto_rename(&mut self, auto_rename: bool) {
+        self.auto_rename = auto_rename;
+    }
+
+    fn auto_rename(&self) -> bool {
+        self.auto_rename
+    }
+
     fn set_network_interface(&mut self, interface: NetworkInterface) {
         self.interface_index = bonjour_util::interface_index(interface);
     }
@@ -100,17 +111,16 @@ impl TMdnsService for BonjourMdnsService {
     fn register(&mut self) -> Result<EventLoop> {
         debug!("Registering service: {:?}", self);
 
-        let txt_len = self
-            .txt_record
-            .as_ref()
-            .map(|t| unsafe { t.inner().get_length() })
-            .unwrap_or(0);
+        let (txt_len, txt_record) = register_txt(self.txt_record.as_ref());
+
+        let flags = if self.auto_rename {
+            constants::BONJOUR_RENAME_FLAGS
+        } else {
+            constants::BONJOUR_NO_RENAME_FLAGS
+        };
 
-        let txt_record = self
-            .txt_record
-            .as_ref()
-            .map(|t| unsafe { t.inner().get_bytes_ptr() })
-            .unwrap_or_null();
+        self.context.name = self.name().map(String::from);
+        self.context.interface_index = self.interface_index;
 
         let mut service_lock = self
             .service
@@ -118,7 +128,7 @@ impl TMdnsService for BonjourMdnsService {
             .expect("should be able to obtain lock on service");
 
         let register_params = RegisterServiceParams::builder()
-            .flags(constants::BONJOUR_RENAME_FLAGS)
+            .flags(flags)
             .interface_index(self.interface_index)
             .name(self.name.as_ref().as_c_chars().unwrap_or_null())
             .regtype(self.kind.as_ptr())
@@ -134,20 +144,44 @@ impl TMdnsService for BonjourMdnsService {
 
         unsafe { service_lock.register_service(register_params)? };
 
-        Ok(EventLoop::new(self.service.clone()))
+        Ok(EventLoop::new(
+            self.service.clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
+        ))
     }
 }
 
+/// Returns the length of and a pointer to the TXT record data to pass to `DNSServiceRegister()`,
+/// in the order it is published.
+fn register_txt(txt_record: Option<&TxtRecord>) -> (u16, *const c_void) {
+    let txt_len = txt_record
+        .map(|t| unsafe { t.inner().get_length() })
+        .unwrap_or(0);
+
+    let txt_record = txt_record
+        .map(|t| unsafe { t.inner().get_bytes_ptr() })
+        .unwrap_or_null();
+
+    (txt_len, txt_record)
+}
+
 #[derive(Default, FromRaw, AsRaw)]
 struct BonjourServiceContext {
+    name: Option<String>,
+    interface_index: u32,
     registered_callback: Option<Box<ServiceRegisteredCallback>>,
     user_context: Option<Arc<dyn Any>>,
+    termination: Termination,
+    dispatcher: Dispatcher,
 }
 
 // Necessary for BonjourMdnsService, cant be `derive`d because of registered_callback
 impl std::fmt::Debug for BonjourServiceContext {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BonjourServiceContext")
+            .field("name", &self.name)
+            .field("interface_index", &self.interface_index)
             .field("user_context", &self.user_context)
             .finish()
     }
@@ -156,11 +190,17 @@ impl std::fmt::Debug for BonjourServiceContext {
 impl BonjourServiceContext {
     fn invoke_callback(&self, result: Result<ServiceRegistration>) {
         if let Some(f) = &self.registered_callback {
-            f(result, self.user_context.clone());
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
         } else {
             warn!("attempted to invoke callback but none was set");
         }
     }
+
+    fn terminate(&self, error: Error) {
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Err(error));
+    }
 }
 
 unsafe extern "system" fn register_callback(
@@ -173,8 +213,12 @@ unsafe extern "system" fn register_callback(
     context: *mut c_void,
 ) {
     let context = BonjourServiceContext::from_raw(context);
-    if let Err(e) = handle_register(context, error, domain, name, regtype) {
-        context.invoke_callback(Err(e));
+
+    match handle_register(context, error, domain, name, regtype) {
+        // the daemon withdraws the registration when it reports an error
+        Err(e) if error != 0 => context.terminate(e),
+        Err(e) => context.invoke_callback(Err(e)),
+        Ok(()) => {}
     }
 }
 
@@ -185,6 +229,12 @@ unsafe fn handle_register(
     name: *const c_char,
     regtype: *const c_char,
 ) -> Result<()> {
+    if error == constants::BONJOUR_ERR_NAME_CONFLICT {
+        return Err(Error::NameConflict(
+            context.name.clone().unwrap_or_default(),
+        ));
+    }
+
     if error != 0 {
         return Err(Error::MdnsSystemError {
             code: error,
@@ -192,6 +242,7 @@ unsafe fn handle_register(
         });
     }
 
+    let full_name = bonjour_util::construct_full_name(name, regtype, domain)?;
     let domain = bonjour_util::normalize_domain(c_str::raw_to_str(domain));
     let kind = bonjour_util::normalize_domain(c_str::raw_to_str(regtype));
 
@@ -199,6 +250,8 @@ unsafe fn handle_register(
         .name(c_str::copy_raw(name))
         .service_type(bonjour_util::parse_regtype(&kind)?)
         .domain(domain)
+        .full_name(full_name)
+        .interface(bonjour_util::interface_from_index(context.interface_index))
         .build()
         .expect("could not build ServiceRegistration");
 
@@ -206,3 +259,26 @@ unsafe fn handle_register(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::slice;
+
+    #[test]
+    fn register_txt_is_published_in_order() {
+        crate::tests::setup();
+
+        let mut record = TxtRecord::new();
+        record.insert("foo", "1").unwrap();
+        record.insert("txtvers", "1").unwrap();
+        record.insert("rp", "3").unwrap();
+
+        let record = record.with_order(&["txtvers", "rp"]);
+        let (txt_len, txt_record) = register_txt(Some(&record));
+
+        let bytes = unsafe { slice::from_raw_parts(txt_record as *const u8, txt_len as usize) };
+
+        assert_eq!(bytes, b"\x09txtvers=1\x04rp=3\x05foo=1");
+    }
+}
@@ -2,10 +2,11 @@
 
 use crate::{bonjour::bonjour_util, Result};
 use bonjour_sys::{
-    dnssd_sock_t, DNSServiceBrowse, DNSServiceBrowseReply, DNSServiceFlags, DNSServiceGetAddrInfo,
-    DNSServiceGetAddrInfoReply, DNSServiceProcessResult, DNSServiceProtocol, DNSServiceRef,
-    DNSServiceRefDeallocate, DNSServiceRefSockFD, DNSServiceRegister, DNSServiceRegisterReply,
-    DNSServiceResolve, DNSServiceResolveReply,
+    dnssd_sock_t, kDNSServiceFlagsShareConnection, DNSServiceBrowse, DNSServiceBrowseReply,
+    DNSServiceCreateConnection, DNSServiceDomainEnumReply, DNSServiceEnumerateDomains,
+    DNSServiceFlags, DNSServiceGetAddrInfo, DNSServiceGetAddrInfoReply, DNSServiceProcessResult,
+    DNSServiceProtocol, DNSServiceRef, DNSServiceRefDeallocate, DNSServiceRefSockFD,
+    DNSServiceRegister, DNSServiceRegisterReply, DNSServiceResolve, DNSServiceResolveReply,
 };
 use libc::{c_char, c_void};
 use std::ptr;
@@ -28,6 +29,39 @@ impl ManagedDNSServiceRef {
         Self(ptr::null_mut())
     }
 
+    /// Returns a new `ManagedDNSServiceRef` for starting one operation on the shared connection
+    /// `connection`, as returned by `connection()` after `create_connection()`.
+    ///
+    /// The operation must be started with `kDNSServiceFlagsShareConnection`, and the returned
+    /// `ManagedDNSServiceRef` must be dropped before the one that owns the connection; its
+    /// results are processed with `process_result()` on the connection.
+    ///
+    /// # Safety
+    /// This function is unsafe because `connection` must be a valid shared connection.
+    pub unsafe fn shared(connection: DNSServiceRef) -> Self {
+        Self(connection)
+    }
+
+    /// Delegate function for [`DNSServiceCreateConnection`]. Operations are started on the
+    /// connection with `ManagedDNSServiceRef::shared()`.
+    ///
+    /// [`DNSServiceCreateConnection`]: https://developer.apple.com/documentation/dnssd/1804724-dnsservicecreateconnection?language=objc
+    ///
+    /// # Safety
+    /// This function is unsafe because it calls a C function.
+    pub unsafe fn create_connection(&mut self) -> Result<()> {
+        bonjour_util::sys_exec(
+            || DNSServiceCreateConnection(&mut self.0 as *mut DNSServiceRef),
+            "could not create connection",
+        )
+    }
+
+    /// Returns the underlying `DNSServiceRef`, for sharing its connection with
+    /// `ManagedDNSServiceRef::shared()`.
+    pub fn connection(&self) -> DNSServiceRef {
+        self.0
+    }
+
     /// Delegate function for [`DNSServiceRegister`].
     ///
     /// [`DNSServiceRegister`]: https://developer.apple.com/documentation/dnssd/1804733-dnsserviceregister?language=objc
@@ -104,13 +138,55 @@ impl ManagedDNSServiceRef {
         )
     }
 
-    /// Delegate function fro [`DNSServiceResolve`].
+    /// Delegate function for [`DNSServiceEnumerateDomains`].
+    ///
+    /// [`DNSServiceEnumerateDomains`]: https://developer.apple.com/documentation/dnssd/1804754-dnsserviceenumeratedomains?language=objc
+    ///
+    /// # Safety
+    /// This function is unsafe because it calls a C function.
+    pub unsafe fn enumerate_domains(
+        &mut self,
+        EnumerateDomainsParams {
+            flags,
+            interface_index,
+            callback,
+            context,
+        }: EnumerateDomainsParams,
+    ) -> Result<()> {
+        bonjour_util::sys_exec(
+            || {
+                DNSServiceEnumerateDomains(
+                    &mut self.0 as *mut DNSServiceRef,
+                    flags,
+                    interface_index,
+                    callback,
+                    context,
+                )
+            },
+            "could not enumerate domains",
+        )
+    }
+
+    /// Delegate function fro [`DNSServiceResolve`]. Blocks until the first result has been
+    /// processed.
     ///
     /// [`DNSServiceResolve`]: https://developer.apple.com/documentation/dnssd/1804744-dnsserviceresolve?language=objc
     ///
     /// # Safety
     /// This function is unsafe because it calls a C function.
-    pub unsafe fn resolve_service(
+    pub unsafe fn resolve_service(&mut self, params: ServiceResolveParams) -> Result<()> {
+        self.start_resolve_service(params)?;
+        self.process_result()
+    }
+
+    /// Delegate function for [`DNSServiceResolve`]. Unlike `resolve_service()`, this does not
+    /// wait for a result; results are processed with `process_result()`.
+    ///
+    /// [`DNSServiceResolve`]: https://developer.apple.com/documentation/dnssd/1804744-dnsserviceresolve?language=objc
+    ///
+    /// # Safety
+    /// This function is unsafe because it calls a C function.
+    pub unsafe fn start_resolve_service(
         &mut self,
         ServiceResolveParams {
             flags,
@@ -122,7 +198,7 @@ impl ManagedDNSServiceRef {
             context,
         }: ServiceResolveParams,
     ) -> Result<()> {
-        bonjour_util::sys_exec(
+        let result = bonjour_util::sys_exec(
             || {
                 DNSServiceResolve(
                     &mut self.0 as *mut DNSServiceRef,
@@ -136,9 +212,11 @@ impl ManagedDNSServiceRef {
                 )
             },
             "DNSServiceResolve() reported error",
-        )?;
+        );
 
-        self.process_result()
+        self.release_failed_share(flags, &result);
+
+        result
     }
 
     /// Delegate function for [`DNSServiceGetAddrInfo`].
@@ -147,7 +225,19 @@ impl ManagedDNSServiceRef {
     ///
     /// # Safety
     /// This function is unsafe because it calls a C function.
-    pub unsafe fn get_address_info(
+    pub unsafe fn get_address_info(&mut self, params: GetAddressInfoParams) -> Result<()> {
+        self.start_get_address_info(params)?;
+        self.process_result()
+    }
+
+    /// Delegate function for [`DNSServiceGetAddrInfo`]. Unlike `get_address_info()`, this does
+    /// not wait for a result; results are processed with `process_result()`.
+    ///
+    /// [`DNSServiceGetAddrInfo`]: https://developer.apple.com/documentation/dnssd/1804700-dnsservicegetaddrinfo?language=objc
+    ///
+    /// # Safety
+    /// This function is unsafe because it calls a C function.
+    pub unsafe fn start_get_address_info(
         &mut self,
         GetAddressInfoParams {
             flags,
@@ -158,7 +248,7 @@ impl ManagedDNSServiceRef {
             context,
         }: GetAddressInfoParams,
     ) -> Result<()> {
-        bonjour_util::sys_exec(
+        let result = bonjour_util::sys_exec(
             || {
                 DNSServiceGetAddrInfo(
                     &mut self.0 as *mut DNSServiceRef,
@@ -171,9 +261,19 @@ impl ManagedDNSServiceRef {
                 )
             },
             "DNSServiceGetAddrInfo() reported error",
-        )?;
+        );
 
-        self.process_result()
+        self.release_failed_share(flags, &result);
+
+        result
+    }
+
+    /// Forgets the shared connection if an operation failed to start on it, so that it is not
+    /// deallocated on `trait Drop`.
+    fn release_failed_share(&mut self, flags: DNSServiceFlags, result: &Result<()>) {
+        if result.is_err() && flags & kDNSServiceFlagsShareConnection != 0 {
+            self.0 = ptr::null_mut();
+        }
     }
 
     /// Delegate function for [`DNSServiceProcessResult`].
@@ -245,6 +345,15 @@ pub struct BrowseServicesParams {
     context: *mut c_void,
 }
 
+/// Holds parameters for `ManagedDNSServiceRef::enumerate_domains()`.
+#[derive(Builder, BuilderDelegate)]
+pub struct EnumerateDomainsParams {
+    flags: DNSServiceFlags,
+    interface_index: u32,
+    callback: DNSServiceDomainEnumReply,
+    context: *mut c_void,
+}
+
 /// Holds parameters for `ManagedDNSServiceRef::resolve_service()`.
 #[derive(Builder, BuilderDelegate)]
 pub struct ServiceResolveParams {
@@ -0,0 +1,400 @@
+//! Bonjour implementation for cross-platform resolver
+
+use super::service_ref::{GetAddressInfoParams, ManagedDNSServiceRef, ServiceResolveParams};
+use super::txt_record_ref::ManagedTXTRecordRef;
+use super::{bonjour_util, constants};
+use crate::event_loop::{Dispatcher, Termination};
+use crate::ffi::{self, c_str, AsRaw, FromRaw};
+use crate::resolver::ResolveBatch;
+use crate::{prelude::*, Error};
+use crate::{
+    EventLoop, NetworkInterface, Result, ServiceAddress, ServiceDiscovery, ServiceKey,
+    ServiceResolvedCallback, ServiceType, TxtRecord,
+};
+use bonjour_sys::{DNSServiceErrorType, DNSServiceFlags, DNSServiceRef};
+use libc::{c_char, c_uchar, c_void};
+use std::any::Any;
+use std::ffi::CString;
+use std::fmt::{self, Formatter};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct BonjourMdnsResolver {
+    // dropped before `service`, which owns the connection the context's operations share
+    context: Box<BonjourResolverContext>,
+    service: Arc<Mutex<ManagedDNSServiceRef>>,
+    name: CString,
+    kind: CString,
+    domain: Option<CString>,
+    interface_index: u32,
+}
+
+impl TMdnsResolver for BonjourMdnsResolver {
+    fn new(service_type: ServiceType, name: &str) -> Self {
+        Self {
+            context: Box::new(BonjourResolverContext {
+                name: name.to_string(),
+                service_type: service_type.clone(),
+                ..Default::default()
+            }),
+            service: Arc::default(),
+            name: c_string!(name),
+            kind: c_string!(format!(
+                "_{}._{}",
+                service_type.name(),
+                service_type.protocol()
+            )),
+            domain: None,
+            interface_index: constants::BONJOUR_IF_UNSPEC,
+        }
+    }
+
+    fn set_network_interface(&mut self, interface: NetworkInterface) {
+        self.interface_index = bonjour_util::interface_index(interface);
+    }
+
+    fn network_interface(&self) -> NetworkInterface {
+        bonjour_util::interface_from_index(self.interface_index)
+    }
+
+    fn set_domain(&mut self, domain: &str) {
+        self.domain = Some(c_string!(domain));
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.domain.as_ref().map(c_str::to_str)
+    }
+
+    fn set_resolved_callback(&mut self, resolved_callback: Box<ServiceResolvedCallback>) {
+        self.context.resolved_callback = Some(resolved_callback);
+    }
+
+    fn set_context(&mut self, context: Box<dyn Any>) {
+        self.context.user_context = Some(Arc::from(context));
+    }
+
+    fn context(&self) -> Option<&dyn Any> {
+        self.context.user_context.as_ref().map(|c| c.as_ref())
+    }
+
+    fn resolve(&mut self) -> Result<EventLoop> {
+        debug!("Resolving service: {:?}", self);
+
+        let connection = {
+            let mut service_lock = self
+                .service
+                .lock()
+                .expect("should have been able to obtain lock on service ref");
+
+            // the address lookup started once the service is resolved shares this connection,
+            // so that both are processed by the `EventLoop` without blocking
+            unsafe { service_lock.create_connection()? };
+
+            service_lock.connection()
+        };
+
+        unsafe { self.start(connection)? };
+
+        Ok(EventLoop::new(
+            self.service.clone(),
+            self.context.termination.clone(),
+            self.context.dispatcher.clone(),
+        ))
+    }
+
+    fn resolve_many(
+        keys: &[ServiceKey],
+        concurrency: usize,
+        timeout: Duration,
+    ) -> Vec<Result<ServiceDiscovery>> {
+        // declared first, so that it is dropped after the resolvers sharing it
+        let mut connection = ManagedDNSServiceRef::default();
+        let mut batch = ResolveBatch::new(keys, concurrency, timeout);
+
+        let result = unsafe {
+            connection
+                .create_connection()
+                .and_then(|_| run_batch(&mut batch, &connection))
+        };
+
+        if let Err(e) = result {
+            batch.fail(&e);
+        }
+
+        batch.into_results()
+    }
+}
+
+impl BonjourMdnsResolver {
+    /// Starts resolving the service on the specified shared connection, as returned by
+    /// `ManagedDNSServiceRef::connection()`, whose results are processed by the caller.
+    unsafe fn start(&mut self, connection: DNSServiceRef) -> Result<()> {
+        // unlike browsing and registering, resolving requires the domain to be specified
+        let default_domain = c_string!("local.");
+        let domain = self.domain.as_ref().unwrap_or(&default_domain);
+
+        self.context.domain = bonjour_util::normalize_domain(c_str::to_str(domain));
+        self.context.connection = Some(connection);
+
+        let resolve_params = ServiceResolveParams::builder()
+            .flags(bonjour_sys::kDNSServiceFlagsShareConnection)
+            .interface_index(self.interface_index)
+            .name(self.name.as_ptr())
+            .regtype(self.kind.as_ptr())
+            .domain(domain.as_ptr())
+            .callback(Some(resolve_callback))
+            .context(self.context.as_raw())
+            .build()
+            .map_err(Error::BrowserError)?;
+
+        let mut resolve = ManagedDNSServiceRef::shared(connection);
+
+        resolve.start_resolve_service(resolve_params)?;
+
+        self.context.resolve = Some(resolve);
+
+        Ok(())
+    }
+}
+
+/// Runs the resolutions of `batch` on the shared `connection`, waiting on its socket until the
+/// next resolution times out or every key has completed.
+unsafe fn run_batch(
+    batch: &mut ResolveBatch<BonjourMdnsResolver>,
+    connection: &ManagedDNSServiceRef,
+) -> Result<()> {
+    loop {
+        batch.start_queued(|resolver| resolver.start(connection.connection()));
+
+        let timeout = match batch.next_timeout() {
+            Some(timeout) => timeout,
+            None => return Ok(()),
+        };
+
+        if ffi::bonjour::read_select(connection.sock_fd(), timeout)? > 0 {
+            connection.process_result()?;
+        }
+
+        batch.collect();
+    }
+}
+
+#[derive(Default, FromRaw, AsRaw)]
+struct BonjourResolverContext {
+    resolved_callback: Option<Box<ServiceResolvedCallback>>,
+    name: String,
+    service_type: ServiceType,
+    domain: String,
+    resolved_port: Option<u16>,
+    resolved_txt: Option<TxtRecord>,
+    resolved_host_name: Option<String>,
+    resolved_addresses: Vec<ServiceAddress>,
+    resolved_ttl: Option<u32>,
+    user_context: Option<Arc<dyn Any>>,
+    connection: Option<DNSServiceRef>,
+    resolve: Option<ManagedDNSServiceRef>,
+    address_info: Option<ManagedDNSServiceRef>,
+    termination: Termination,
+    dispatcher: Dispatcher,
+}
+
+impl BonjourResolverContext {
+    fn invoke_callback(&self, result: Result<ServiceDiscovery>) {
+        if let Some(f) = &self.resolved_callback {
+            self.dispatcher
+                .dispatch(|| f(result, self.user_context.clone()));
+        } else {
+            warn!("attempted to invoke callback but none was set");
+        }
+    }
+
+    fn terminate(&self, error: Error) {
+        self.termination.terminate(error.to_string());
+        self.invoke_callback(Err(error));
+    }
+}
+
+impl fmt::Debug for BonjourResolverContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BonjourResolverContext")
+            .field("name", &self.name)
+            .field("service_type", &self.service_type)
+            .field("domain", &self.domain)
+            .field("resolved_port", &self.resolved_port)
+            .finish()
+    }
+}
+
+unsafe extern "system" fn resolve_callback(
+    _sd_ref: DNSServiceRef,
+    _flags: DNSServiceFlags,
+    interface_index: u32,
+    error: DNSServiceErrorType,
+    _fullname: *const c_char,
+    host_target: *const c_char,
+    port: u16,
+    txt_len: u16,
+    txt_record: *const c_uchar,
+    context: *mut c_void,
+) {
+    let ctx = BonjourResolverContext::from_raw(context);
+
+    if error != 0 {
+        ctx.terminate(Error::MdnsSystemError {
+            code: error,
+            message: "resolve_callback() reported error".into(),
+        });
+        return;
+    }
+
+    let result = handle_resolve(ctx, port, interface_index, host_target, txt_len, txt_record);
+
+    if let Err(e) = result {
+        ctx.invoke_callback(Err(e));
+    }
+}
+
+unsafe fn handle_resolve(
+    ctx: &mut BonjourResolverContext,
+    port: u16,
+    interface_index: u32,
+    host_target: *const c_char,
+    txt_len: u16,
+    txt_record: *const c_uchar,
+) -> Result<()> {
+    ctx.resolved_port = Some(port);
+    ctx.resolved_addresses.clear();
+
+    ctx.resolved_txt = if txt_len > 1 {
+        Some(TxtRecord::from(ManagedTXTRecordRef::clone_raw(
+            txt_record, txt_len,
+        )?))
+    } else {
+        None
+    };
+
+    let connection = ctx
+        .connection
+        .ok_or_else(|| Error::BrowserError("resolver has no connection".into()))?;
+
+    // started on the shared connection, so the result is processed by a later `poll()` instead
+    // of blocking this one
+    let mut address_info = ManagedDNSServiceRef::shared(connection);
+
+    address_info.start_get_address_info(
+        GetAddressInfoParams::builder()
+            .flags(
+                bonjour_sys::kDNSServiceFlagsForceMulticast
+                    | bonjour_sys::kDNSServiceFlagsShareConnection,
+            )
+            .interface_index(interface_index)
+            .protocol(0)
+            .hostname(host_target)
+            .callback(Some(get_address_info_callback))
+            .context(ctx.as_raw())
+            .build()
+            .map_err(Error::BrowserError)?,
+    )?;
+
+    ctx.address_info = Some(address_info);
+
+    Ok(())
+}
+
+unsafe extern "system" fn get_address_info_callback(
+    _sd_ref: DNSServiceRef,
+    flags: DNSServiceFlags,
+    interface_index: u32,
+    error: DNSServiceErrorType,
+    hostname: *const c_char,
+    address: *const bonjour_sys::sockaddr,
+    ttl: u32,
+    context: *mut c_void,
+) {
+    let ctx = BonjourResolverContext::from_raw(context);
+
+    let result =
+        handle_get_address_info(ctx, flags, error, interface_index, address, hostname, ttl);
+
+    if let Err(e) = result {
+        ctx.invoke_callback(Err(e));
+    }
+}
+
+unsafe fn handle_get_address_info(
+    ctx: &mut BonjourResolverContext,
+    flags: DNSServiceFlags,
+    error: DNSServiceErrorType,
+    interface_index: u32,
+    address: *const bonjour_sys::sockaddr,
+    hostname: *const c_char,
+    ttl: u32,
+) -> Result<()> {
+    // results may still arrive after the service has been reported
+    if ctx.resolved_port.is_none() {
+        return Ok(());
+    }
+
+    if error != 0 {
+        ctx.resolved_port = None;
+        ctx.resolved_addresses.clear();
+
+        return Err(Error::MdnsSystemError {
+            code: error,
+            message: "get_address_info_callback() reported error".into(),
+        });
+    }
+
+    if flags & bonjour_sys::kDNSServiceFlagsAdd != 0 {
+        match bonjour_util::sockaddr_to_ip(address) {
+            Some(address) => ctx.resolved_addresses.push(
+                ServiceAddress::builder()
+                    .address(address)
+                    .interface(bonjour_util::interface_from_index(interface_index))
+                    .build()
+                    .expect("could not build ServiceAddress"),
+            ),
+            None => warn!("ignoring resolved address of unsupported family"),
+        }
+
+        ctx.resolved_host_name = Some(c_str::copy_raw(hostname));
+        ctx.resolved_ttl = Some(ctx.resolved_ttl.map_or(ttl, |t| t.min(ttl)));
+    }
+
+    // collect every address of the host, until Bonjour reports that no more are coming
+    if flags & bonjour_sys::kDNSServiceFlagsMoreComing != 0 || ctx.resolved_addresses.is_empty() {
+        return Ok(());
+    }
+
+    let addresses = mem::take(&mut ctx.resolved_addresses);
+    let first = addresses[0].clone();
+
+    let hostname = ctx.resolved_host_name.take().unwrap_or_default();
+    let local_host = bonjour_util::is_local_host_name(&hostname);
+
+    let result = ServiceDiscovery::builder()
+        .name(ctx.name.clone())
+        .service_type(ctx.service_type.clone())
+        .domain(ctx.domain.clone())
+        .host_name(hostname)
+        .address(first.address().to_string())
+        // on macOS the bytes are swapped for the port
+        .port(ctx.resolved_port.take().unwrap_or_default().to_be())
+        .txt(ctx.resolved_txt.take())
+        .local_host(local_host)
+        .interface(*first.interface())
+        .ttl(
+            ctx.resolved_ttl
+                .take()
+                .map(|ttl| Duration::from_secs(ttl.into())),
+        )
+        .addresses(addresses)
+        .build()
+        .expect("could not build ServiceDiscovery");
+
+    ctx.invoke_callback(Ok(result));
+
+    Ok(())
+}
@@ -2,7 +2,7 @@
 
 use super::txt_record_ref::ManagedTXTRecordRef;
 use crate::ffi::c_str;
-use crate::txt_record::TTxtRecord;
+use crate::txt_record::{self, TTxtRecord};
 use crate::Result;
 use libc::{c_char, c_void};
 use std::ffi::CString;
@@ -80,6 +80,32 @@ impl TTxtRecord for BonjourTxtRecord {
     fn values<'a>(&'a self) -> Box<dyn Iterator<Item = String> + 'a> {
         Box::new(Values(Iter::new(self)))
     }
+
+    fn with_order(self, keys: &[&str]) -> Self {
+        let mut record = Self::new();
+
+        for (key, value) in txt_record::ordered_entries(self.iter().collect(), keys) {
+            record
+                .insert(&key, &value)
+                .expect("could not insert key/value pair");
+        }
+
+        record
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        if self.is_empty() {
+            return vec![0];
+        }
+
+        unsafe {
+            slice::from_raw_parts(
+                self.0.get_bytes_ptr() as *const u8,
+                self.0.get_length() as usize,
+            )
+            .to_vec()
+        }
+    }
 }
 
 impl Clone for BonjourTxtRecord {
@@ -1,16 +1,51 @@
 //! Trait definition for cross-platform browser
 
-use crate::{EventLoop, NetworkInterface, Result, ServiceType, TxtRecord};
+use crate::prelude::*;
+use crate::{Error, EventLoop, IpProtocol, NetworkInterface, Result, ServiceType, TxtRecord};
 use std::any::Any;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr, SocketAddrV6, ToSocketAddrs};
+use std::option;
+use std::sync::mpsc::{self, Receiver};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Event from [`MdnsBrowser`] received by the `ServiceBrowserCallback`.
 ///
 /// [`MdnsBrowser`]: type.MdnsBrowser.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "event", content = "data", rename_all = "snake_case")
+)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BrowserEvent {
     Add(ServiceDiscovery),
+    /// A service was found but not resolved, because automatic resolution is disabled.
+    Found(ServiceInstance),
     Remove(ServiceRemoval),
+    /// All services currently known have been reported and no more events are expected
+    /// immediately. Useful to batch updates or to detect the end of the initial results.
+    ///
+    /// On Bonjour, this is reported after each batch of events, once `kDNSServiceFlagsMoreComing`
+    /// is no longer set.
+    AllForNow,
+    /// All services in the local cache have been reported; further events depend on network
+    /// traffic. Only reported by Avahi.
+    CacheExhausted,
+    /// The browser stopped because the connection to the mDNS daemon was closed. No further
+    /// events will be delivered.
+    Stopped,
+    /// The browser failed for the specified reason. No further events will be delivered.
+    Failed(String),
+    /// Two different hosts are advertising the same service instance name. Reported after the
+    /// `Add` of the duplicate, to help find misconfigured peers.
+    DuplicateNameDetected(Box<DuplicateName>),
+    /// A found service was not resolved within the timeout set with
+    /// `TMdnsBrowser::set_resolve_timeout()` and has been abandoned, e.g. because it is a stale
+    /// record of a host that has left the network.
+    ResolveTimedOut(ServiceInstance),
 }
 
 /// Interface for interacting with underlying mDNS implementation service browsing capabilities.
@@ -27,12 +62,59 @@ pub trait TMdnsBrowser {
     /// Returns the network interface on which to browse for services on.
     fn network_interface(&self) -> NetworkInterface;
 
+    /// Sets the domain in which to browse for services, e.g. `example.com` for wide-area
+    /// DNS-SD.
+    ///
+    /// Most applications will want to use the default domain, which is usually `local`.
+    fn set_domain(&mut self, domain: &str);
+
+    /// Returns the domain in which to browse for services, or `None` for the default domain.
+    fn domain(&self) -> Option<&str>;
+
+    /// Sets whether discovered services should be resolved automatically. Defaults to `true`.
+    ///
+    /// When disabled, discovered services are reported as `BrowserEvent::Found` with only their
+    /// name, type, domain and interface, saving the traffic and time of resolving their host,
+    /// address, port and TXT record.
+    fn set_auto_resolve(&mut self, auto_resolve: bool);
+
+    /// Returns true if discovered services will be resolved automatically.
+    fn auto_resolve(&self) -> bool;
+
+    /// Sets the time after which the resolution of a discovered service is abandoned and
+    /// reported as `BrowserEvent::ResolveTimedOut`, or `None` to leave it to the mDNS daemon.
+    /// Defaults to `None`.
+    ///
+    /// On Bonjour, services are resolved one at a time while the browser's `EventLoop` is
+    /// polled, so a resolution also blocks polling for up to this long.
+    fn set_resolve_timeout(&mut self, timeout: Option<Duration>);
+
+    /// Returns the time after which the resolution of a discovered service is abandoned.
+    fn resolve_timeout(&self) -> Option<Duration>;
+
     /// Sets the [`ServiceBrowserCallback`] that is invoked when the browser has discovered and
     /// resolved or removed a service.
     ///
     /// [`ServiceBrowserCallback`]: ../type.ServiceBrowserCallback.html
     fn set_service_callback(&mut self, service_callback: Box<ServiceBrowserCallback>);
 
+    /// Returns a `Receiver` that browser events are sent to instead of a
+    /// [`ServiceBrowserCallback`], replacing any callback previously set.
+    ///
+    /// Events are still only produced while the `EventLoop` is being polled.
+    ///
+    /// [`ServiceBrowserCallback`]: ../type.ServiceBrowserCallback.html
+    fn service_channel(&mut self) -> Receiver<Result<BrowserEvent>> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.set_service_callback(Box::new(move |result, _| {
+            // the receiver may have been dropped, in which case the event is not of interest
+            let _ = sender.send(result);
+        }));
+
+        receiver
+    }
+
     /// Sets the optional user context to pass through to the callback. This is useful if you need
     /// to share state between pre and post-callback. The context type must implement `Any`.
     fn set_context(&mut self, context: Box<dyn Any>);
@@ -42,6 +124,62 @@ pub trait TMdnsBrowser {
 
     /// Starts the browser. Returns an `EventLoop` which can be called to keep the browser alive.
     fn browse_services(&mut self) -> Result<EventLoop>;
+
+    /// Starts the browser and polls it for the specified `duration`, returning the services that
+    /// were discovered and not removed again within that time.
+    ///
+    /// Services are reported once per name and domain, with the latest resolution, and are only
+    /// left out once they have been removed from every interface and protocol they were resolved
+    /// on.
+    ///
+    /// This replaces any [`ServiceBrowserCallback`] previously set. Errors resolving individual
+    /// services are logged and skipped.
+    ///
+    /// [`ServiceBrowserCallback`]: ../type.ServiceBrowserCallback.html
+    fn browse_for(&mut self, duration: Duration) -> Result<Vec<ServiceDiscovery>> {
+        let events = self.service_channel();
+        let event_loop = self.browse_services()?;
+        let deadline = Instant::now() + duration;
+        let mut discoveries: Vec<ServiceDiscovery> = Vec::new();
+        let mut resolutions = Resolutions::default();
+
+        while Instant::now() < deadline {
+            // returns `Error::Terminated` once the browser has stopped or failed
+            event_loop.poll_until(deadline)?;
+
+            for event in events.try_iter() {
+                match event {
+                    Ok(BrowserEvent::Add(discovery)) => {
+                        resolutions.add(
+                            (discovery.name.clone(), discovery.domain.clone()),
+                            &discovery,
+                        );
+
+                        let existing = discoveries
+                            .iter_mut()
+                            .find(|d| d.name == discovery.name && d.domain == discovery.domain);
+
+                        match existing {
+                            Some(existing) => *existing = discovery,
+                            None => discoveries.push(discovery),
+                        }
+                    }
+                    Ok(BrowserEvent::Remove(removal)) => {
+                        let key = (removal.name.clone(), removal.domain.clone());
+
+                        if resolutions.remove(&key, &removal) && resolutions.get(&key).is_none() {
+                            discoveries
+                                .retain(|d| d.name != removal.name || d.domain != removal.domain);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("error while browsing: {}", e),
+                }
+            }
+        }
+
+        Ok(discoveries)
+    }
 }
 
 /// Callback invoked from [`MdnsBrowser`] once a service has been discovered and resolved or
@@ -54,6 +192,26 @@ pub trait TMdnsBrowser {
 /// [`MdnsBrowser`]: type.MdnsBrowser.html
 pub type ServiceBrowserCallback = dyn Fn(Result<BrowserEvent>, Option<Arc<dyn Any>>);
 
+/// TXT record key conventionally holding a stable identifier, such as a UUID, for the peer
+/// advertising a service. Unlike the service name, this identifier survives renames.
+pub const TXT_PEER_ID_KEY: &str = "peerid";
+
+/// Represents a service that has been found by a [`MdnsBrowser`] without being resolved.
+///
+/// [`MdnsBrowser`]: type.MdnsBrowser.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Getters, Builder, BuilderDelegate, Clone, PartialEq, Eq)]
+pub struct ServiceInstance {
+    /// The "abc" part in "abc._http._udp.local"
+    name: String,
+    /// The "_http._udp" part in "abc._http._udp.local"
+    kind: String,
+    /// The "local" part in "abc._http._udp.local"
+    domain: String,
+    /// The network interface the service was found on
+    interface: NetworkInterface,
+}
+
 /// Represents a service that has been discovered by a [`MdnsBrowser`].
 ///
 /// [`MdnsBrowser`]: type.MdnsBrowser.html
@@ -67,11 +225,191 @@ pub struct ServiceDiscovery {
     address: String,
     port: u16,
     txt: Option<TxtRecord>,
+    #[getter(skip)]
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    local_host: bool,
+    /// The network interface the service was resolved on
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    interface: NetworkInterface,
+    /// The IP protocol the service was resolved over
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    protocol: IpProtocol,
+    /// The remaining time to live of the resolved address record, if reported by the backend.
+    /// Only Bonjour reports it; Avahi tracks expiry itself and reports `BrowserEvent::Remove`.
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    ttl: Option<Duration>,
+    /// Every address the service was resolved to, including `address`.
+    ///
+    /// Bonjour collects all the addresses of the host in one resolution. Avahi resolves each
+    /// interface and protocol separately and reports every address in its own
+    /// `BrowserEvent::Add`, so there this only holds `address`.
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    addresses: Vec<ServiceAddress>,
+}
+
+/// An address a [`ServiceDiscovery`] was resolved to.
+///
+/// [`ServiceDiscovery`]: struct.ServiceDiscovery.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Getters, Builder, BuilderDelegate, Clone, PartialEq, Eq)]
+pub struct ServiceAddress {
+    /// The resolved address; its variant is the protocol it was resolved with
+    address: IpAddr,
+    /// The network interface the address was resolved on
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    interface: NetworkInterface,
+}
+
+impl ServiceDiscovery {
+    /// Returns true if this service is being advertised by the machine this process is running
+    /// on, e.g. by another process on the same host.
+    ///
+    /// On Avahi this is reported by the daemon. Bonjour does not flag local results, so there it
+    /// is a heuristic comparing the first label of the service's host name with the machine's host
+    /// name (`gethostname()` on Apple platforms, `COMPUTERNAME` on Windows). It reports false for a
+    /// local service registered with a custom host name, or after Bonjour has renamed the host to
+    /// resolve a conflict.
+    pub fn is_local_host(&self) -> bool {
+        self.local_host
+    }
+
+    /// Returns a stable identifier for the peer advertising this service.
+    ///
+    /// This is the value published under [`TXT_PEER_ID_KEY`] if present, so that a renamed peer
+    /// (e.g. "Office Printer (2)") is still recognized, otherwise the service name.
+    ///
+    /// [`TXT_PEER_ID_KEY`]: constant.TXT_PEER_ID_KEY.html
+    pub fn peer_id(&self) -> String {
+        self.txt
+            .as_ref()
+            .and_then(|txt| txt.get(TXT_PEER_ID_KEY))
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    /// Returns the resolved address of this service as an `IpAddr`.
+    ///
+    /// Any IPv6 zone suffix (e.g. `%eth0`) is ignored; see [`socket_addr()`] to retain the scope
+    /// of link-local addresses.
+    ///
+    /// [`socket_addr()`]: #method.socket_addr
+    pub fn ip_addr(&self) -> Result<IpAddr> {
+        let address = self.address.split('%').next().unwrap_or_default();
+
+        address
+            .parse()
+            .map_err(|_| Error::BrowserError(format!("invalid service address `{}`", self.address)))
+    }
+
+    /// Returns the resolved address and port of this service as a `SocketAddr`.
+    ///
+    /// Link-local IPv6 addresses are scoped to the interface the service was resolved on, so
+    /// that they can be connected to directly.
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        Ok(match self.ip_addr()? {
+            IpAddr::V6(address) if address.segments()[0] & 0xffc0 == 0xfe80 => {
+                let scope_id = match self.interface {
+                    NetworkInterface::AtIndex(index) => index,
+                    NetworkInterface::Unspec => 0,
+                };
+
+                SocketAddrV6::new(address, self.port, 0, scope_id).into()
+            }
+            address => SocketAddr::new(address, self.port),
+        })
+    }
+}
+
+impl ToSocketAddrs for ServiceDiscovery {
+    type Iter = option::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> std::io::Result<Self::Iter> {
+        self.socket_addr()
+            .map(|address| Some(address).into_iter())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+    }
+}
+
+/// Represents a service instance name that is advertised by more than one host.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Getters, Clone, PartialEq, Eq)]
+pub struct DuplicateName {
+    /// The service previously discovered under this name
+    existing: ServiceDiscovery,
+    /// The service on another host that was discovered under the same name
+    duplicate: ServiceDiscovery,
+}
+
+/// Tracks the host advertising each discovered service instance, to detect names advertised by
+/// more than one host.
+///
+/// Backends report an instance once per interface (and, on Avahi, per protocol) it is found on,
+/// so the resolutions of each instance are counted per interface and the instance is forgotten
+/// once all of them have been removed.
+#[derive(Debug, Default)]
+pub(crate) struct DuplicateNameDetector(
+    HashMap<(String, String), HashMap<NetworkInterface, (ServiceDiscovery, usize)>>,
+);
+
+impl DuplicateNameDetector {
+    /// Records the specified discovery, returning a `DuplicateName` if its name was previously
+    /// discovered on a different host.
+    pub fn observe(&mut self, service: &ServiceDiscovery) -> Option<DuplicateName> {
+        let key = (service.name.clone(), service.domain.clone());
+        let resolutions = self.0.entry(key).or_default();
+
+        let existing = resolutions
+            .values()
+            .map(|(existing, _)| existing)
+            .find(|existing| !existing.host_name.eq_ignore_ascii_case(&service.host_name))
+            .cloned();
+
+        let resolution = resolutions
+            .entry(service.interface)
+            .or_insert_with(|| (service.clone(), 0));
+
+        resolution.0 = service.clone();
+        resolution.1 += 1;
+
+        existing.map(|existing| DuplicateName {
+            existing,
+            duplicate: service.clone(),
+        })
+    }
+
+    /// Forgets a resolution of the service with the specified name and domain on the specified
+    /// interface, after it has been removed.
+    pub fn remove(&mut self, name: &str, domain: &str, interface: NetworkInterface) {
+        let key = (name.to_string(), domain.to_string());
+
+        let resolutions = match self.0.get_mut(&key) {
+            Some(resolutions) => resolutions,
+            None => return,
+        };
+
+        if let Some(resolution) = resolutions.get_mut(&interface) {
+            resolution.1 -= 1;
+
+            if resolution.1 == 0 {
+                resolutions.remove(&interface);
+            }
+        }
+
+        if resolutions.is_empty() {
+            self.0.remove(&key);
+        }
+    }
 }
 
 /// Represents a service that has been removed by a [`MdnsBrowser`].
 ///
 /// [`MdnsBrowser`]: type.MdnsBrowser.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Getters, Builder, BuilderDelegate, Clone, PartialEq, Eq)]
 pub struct ServiceRemoval {
     /// The "abc" part in "abc._http._udp.local"
@@ -80,4 +418,269 @@ pub struct ServiceRemoval {
     kind: String,
     /// The "local" part in "abc._http._udp.local"
     domain: String,
+    /// The network interface the service was removed from
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    interface: NetworkInterface,
+    /// The IP protocol the service was removed from
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    protocol: IpProtocol,
+}
+
+/// Tracks the resolutions of service instances by the interface and protocol they were
+/// resolved on, so that an instance is only considered gone once every resolution that was
+/// reported with `BrowserEvent::Add` has been removed.
+///
+/// Avahi reports a `BrowserEvent::Remove` for every interface and protocol an instance was
+/// browsed on, including those whose resolution failed or timed out. Removals of those are
+/// ignored.
+#[derive(Debug)]
+pub(crate) struct Resolutions<K>(
+    HashMap<K, HashMap<(NetworkInterface, IpProtocol), ServiceDiscovery>>,
+);
+
+impl<K> Default for Resolutions<K> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<K: Eq + Hash> Resolutions<K> {
+    /// Records the specified resolution of `key`, replacing any previous resolution on the same
+    /// interface and protocol.
+    pub fn add(&mut self, key: K, service: &ServiceDiscovery) {
+        self.0
+            .entry(key)
+            .or_default()
+            .insert((service.interface, service.protocol), service.clone());
+    }
+
+    /// Forgets the resolution of `key` withdrawn by `removal`, returning false if `key` was not
+    /// resolved on the interface and protocol it was removed from.
+    pub fn remove(&mut self, key: &K, removal: &ServiceRemoval) -> bool {
+        let resolutions = match self.0.get_mut(key) {
+            Some(resolutions) => resolutions,
+            None => return false,
+        };
+
+        if resolutions
+            .remove(&(removal.interface, removal.protocol))
+            .is_none()
+        {
+            return false;
+        }
+
+        if resolutions.is_empty() {
+            self.0.remove(key);
+        }
+
+        true
+    }
+
+    /// Returns a remaining resolution of `key`, or `None` once it has been removed from every
+    /// interface and protocol it was resolved on.
+    pub fn get(&self, key: &K) -> Option<&ServiceDiscovery> {
+        self.0
+            .get(key)
+            .and_then(|resolutions| resolutions.values().next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery(txt: Option<TxtRecord>) -> ServiceDiscovery {
+        ServiceDiscovery::builder()
+            .name("Office Printer (2)".into())
+            .service_type(ServiceType::new("ipp", "tcp").unwrap())
+            .domain("local".into())
+            .host_name("printer.local".into())
+            .address("192.168.1.2".into())
+            .port(631)
+            .txt(txt)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn peer_id_uses_txt_record() {
+        crate::tests::setup();
+
+        let mut txt = TxtRecord::new();
+        txt.insert(TXT_PEER_ID_KEY, "4c6f6e67").unwrap();
+
+        assert_eq!(discovery(Some(txt)).peer_id(), "4c6f6e67");
+    }
+
+    #[test]
+    fn peer_id_falls_back_to_name() {
+        assert_eq!(discovery(None).peer_id(), "Office Printer (2)");
+    }
+
+    #[test]
+    fn duplicate_name_detector_reports_other_hosts() {
+        let mut detector = DuplicateNameDetector::default();
+        let mut existing = discovery(None);
+        existing.interface = NetworkInterface::AtIndex(1);
+
+        let mut same_host = existing.clone();
+        same_host.address = "fe80::1".into();
+
+        let mut duplicate = discovery(None);
+        duplicate.host_name = "other.local".into();
+        duplicate.interface = NetworkInterface::AtIndex(2);
+
+        assert_eq!(detector.observe(&existing), None);
+        assert_eq!(detector.observe(&same_host), None);
+        assert_eq!(
+            detector.observe(&duplicate),
+            Some(DuplicateName {
+                existing: same_host,
+                duplicate: duplicate.clone(),
+            })
+        );
+
+        detector.remove(duplicate.name(), duplicate.domain(), duplicate.interface);
+        assert_eq!(detector.observe(&existing), None);
+    }
+
+    #[test]
+    fn duplicate_name_detector_forgets_after_every_resolution_is_removed() {
+        let mut detector = DuplicateNameDetector::default();
+        let existing = discovery(None);
+
+        let mut same_host = existing.clone();
+        same_host.address = "fe80::1".into();
+
+        let mut duplicate = discovery(None);
+        duplicate.host_name = "other.local".into();
+        duplicate.interface = NetworkInterface::AtIndex(2);
+
+        detector.observe(&existing);
+        detector.observe(&same_host);
+
+        detector.remove(existing.name(), existing.domain(), existing.interface);
+        assert!(detector.observe(&duplicate).is_some());
+
+        detector.remove(duplicate.name(), duplicate.domain(), duplicate.interface);
+        detector.remove(existing.name(), existing.domain(), existing.interface);
+        assert_eq!(detector.observe(&duplicate), None);
+    }
+
+    fn removal(interface: NetworkInterface, protocol: IpProtocol) -> ServiceRemoval {
+        ServiceRemoval::builder()
+            .name("Office Printer (2)".into())
+            .kind("_ipp._tcp".into())
+            .domain("local".into())
+            .interface(interface)
+            .protocol(protocol)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn resolutions_keep_key_until_every_resolution_is_removed() {
+        let mut resolutions = Resolutions::default();
+
+        let mut v4 = discovery(None);
+        v4.interface = NetworkInterface::AtIndex(1);
+        v4.protocol = IpProtocol::V4;
+
+        let mut v6 = v4.clone();
+        v6.address = "fe80::1".into();
+        v6.protocol = IpProtocol::V6;
+
+        resolutions.add("printer", &v4);
+        resolutions.add("printer", &v6);
+
+        assert!(resolutions.remove(&"printer", &removal(v4.interface, v4.protocol)));
+        assert_eq!(resolutions.get(&"printer"), Some(&v6));
+
+        assert!(resolutions.remove(&"printer", &removal(v6.interface, v6.protocol)));
+        assert_eq!(resolutions.get(&"printer"), None);
+    }
+
+    #[test]
+    fn resolutions_ignore_removal_of_unresolved_pair() {
+        let mut resolutions = Resolutions::default();
+
+        let mut v4 = discovery(None);
+        v4.interface = NetworkInterface::AtIndex(1);
+        v4.protocol = IpProtocol::V4;
+
+        resolutions.add("printer", &v4);
+
+        // e.g. the resolve on this interface and protocol failed or timed out
+        let unresolved = removal(NetworkInterface::AtIndex(2), IpProtocol::V6);
+
+        assert!(!resolutions.remove(&"printer", &unresolved));
+        assert_eq!(resolutions.get(&"printer"), Some(&v4));
+    }
+
+    #[test]
+    fn socket_addr_combines_address_and_port() {
+        assert_eq!(
+            discovery(None).socket_addr().unwrap(),
+            "192.168.1.2:631".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn socket_addr_scopes_link_local_addresses() {
+        let mut discovery = discovery(None);
+        discovery.address = "fe80::1%eth0".into();
+        discovery.interface = NetworkInterface::AtIndex(3);
+
+        assert_eq!(
+            discovery.ip_addr().unwrap(),
+            "fe80::1".parse::<IpAddr>().unwrap()
+        );
+
+        match discovery.socket_addr().unwrap() {
+            SocketAddr::V6(address) => assert_eq!(address.scope_id(), 3),
+            address => panic!("unexpected address: {}", address),
+        }
+    }
+
+    #[test]
+    fn ip_addr_rejects_invalid_address() {
+        let mut discovery = discovery(None);
+        discovery.address = "printer.local".into();
+
+        assert!(discovery.ip_addr().is_err());
+        assert!(discovery.to_socket_addrs().is_err());
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn serialize_success() {
+        crate::tests::setup();
+
+        let mut txt = TxtRecord::new();
+        txt.insert(TXT_PEER_ID_KEY, "4c6f6e67").unwrap();
+
+        let discovery = ServiceDiscovery::builder()
+            .name("Office Printer (2)".into())
+            .service_type(ServiceType::new("ipp", "tcp").unwrap())
+            .domain("local".into())
+            .host_name("printer.local".into())
+            .address("192.168.1.2".into())
+            .port(631)
+            .txt(Some(txt))
+            .interface(NetworkInterface::AtIndex(3))
+            .ttl(Some(Duration::from_secs(120)))
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&discovery).unwrap();
+        let discovery_de: ServiceDiscovery = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(discovery, discovery_de);
+    }
 }
@@ -0,0 +1,93 @@
+//! Network-wide single-instance coordination through service name ownership
+
+use crate::prelude::*;
+use crate::{Error, EventLoop, MdnsService, Result, ServiceRegistration, ServiceType};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const CLAIM_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A service name that has been exclusively claimed on the network by this process.
+///
+/// The claim is held for as long as this value is alive and [`poll()`] continues to be called.
+/// Dropping it withdraws the service and releases the name for other processes to claim.
+///
+/// [`poll()`]: #method.poll
+pub struct ServiceClaim {
+    event_loop: EventLoop,
+    _service: MdnsService,
+    registration: ServiceRegistration,
+}
+
+impl ServiceClaim {
+    /// Attempts to claim `name` for the specified `ServiceType` by registering it with automatic
+    /// renaming disabled.
+    ///
+    /// Returns `Ok(None)` if the name is already held by another process on the network, or an
+    /// error if the outcome could not be determined within `timeout`.
+    pub fn try_acquire(
+        service_type: ServiceType,
+        port: u16,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<Option<Self>> {
+        let mut service = MdnsService::new(service_type, port);
+        let outcome: Rc<RefCell<Option<Result<ServiceRegistration>>>> = Rc::default();
+        let callback_outcome = outcome.clone();
+
+        service.set_name(name);
+        service.set_auto_rename(false);
+        service.set_registered_callback(Box::new(move |result, _| {
+            *callback_outcome.borrow_mut() = Some(result);
+        }));
+
+        let event_loop = service.register()?;
+        let start = Instant::now();
+
+        loop {
+            event_loop.poll(CLAIM_POLL_INTERVAL)?;
+
+            let result = outcome.borrow_mut().take();
+
+            match result {
+                Some(Ok(registration)) => {
+                    return Ok(Some(Self {
+                        event_loop,
+                        _service: service,
+                        registration,
+                    }))
+                }
+                Some(Err(Error::NameConflict(_))) => return Ok(None),
+                Some(Err(e)) => return Err(e),
+                None => {}
+            }
+
+            if start.elapsed() >= timeout {
+                return Err(Error::ServiceError(format!(
+                    "timed out claiming service name `{}`",
+                    name
+                )));
+            }
+        }
+    }
+
+    /// Returns the registration that holds this claim.
+    pub fn registration(&self) -> &ServiceRegistration {
+        &self.registration
+    }
+
+    /// Polls the underlying event loop. This must be called regularly to keep the claim alive.
+    pub fn poll(&self, timeout: Duration) -> Result<()> {
+        self.event_loop.poll(timeout)
+    }
+}
+
+impl fmt::Debug for ServiceClaim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServiceClaim")
+            .field("registration", &self.registration)
+            .finish()
+    }
+}
@@ -0,0 +1,472 @@
+//! Background thread that owns and polls services and browsers
+
+use crate::event_loop::Waker;
+use crate::prelude::*;
+use crate::{
+    BrowserEvent, Error, EventLoop, MdnsBrowser, MdnsService, Result, ServiceRegistration,
+    ServiceType, TxtRecord,
+};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long an operation whose event loop can be woken up waits for events in each `poll()`
+const WAKEABLE_POLL_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// How long an operation whose event loop cannot be woken up waits for events in each `poll()`,
+/// bounding how long it takes to notice that it has been stopped
+const UNWAKEABLE_POLL_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Runs services and browsers on background threads that it owns, so that consumers do not need
+/// to manage their own poll loop.
+///
+/// Each operation runs on its own thread, which waits in `poll()` until its backend has events,
+/// so events are handled as they arrive and idle operations do not use the CPU.
+///
+/// Operations run until they are stopped with [`DaemonOperation::stop()`], they fail, or
+/// [`shutdown()`] is called or the daemon is dropped. Results are delivered through the
+/// [`DaemonOperation`] returned when each operation is started.
+///
+/// [`DaemonOperation`]: struct.DaemonOperation.html
+/// [`DaemonOperation::stop()`]: struct.DaemonOperation.html#method.stop
+/// [`shutdown()`]: #method.shutdown
+pub struct ServiceDaemon {
+    commands: Sender<Command>,
+    thread: Option<JoinHandle<()>>,
+    next_id: AtomicU64,
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+}
+
+/// Clonable handle through which a [`ServiceDaemon`] can be shut down from another thread, e.g.
+/// one waiting for process signals, while its owner keeps using it until then.
+///
+/// [`ServiceDaemon`]: struct.ServiceDaemon.html
+#[derive(Clone)]
+pub struct ShutdownToken {
+    commands: Sender<Command>,
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+}
+
+/// Handle on an operation started by a [`ServiceDaemon`], through which its results are received
+/// and it can be stopped.
+///
+/// Dropping the handle does not stop the operation.
+///
+/// [`ServiceDaemon`]: struct.ServiceDaemon.html
+pub struct DaemonOperation<T> {
+    id: u64,
+    commands: Sender<Command>,
+    receiver: Receiver<Result<T>>,
+}
+
+enum Command {
+    Register {
+        id: u64,
+        service_type: ServiceType,
+        port: u16,
+        name: Option<String>,
+        txt: HashMap<String, String>,
+        results: Sender<Result<ServiceRegistration>>,
+    },
+    Browse {
+        id: u64,
+        service_type: ServiceType,
+        events: Sender<Result<BrowserEvent>>,
+    },
+    Stop(u64),
+    Shutdown,
+}
+
+/// An operation running on its own thread, which owns its service or browser.
+struct Operation {
+    id: u64,
+    stopped: Arc<AtomicBool>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    thread: JoinHandle<()>,
+}
+
+/// A started service or browser, driven by the thread of its `Operation`.
+struct Running {
+    event_loop: EventLoop,
+    /// Delivers an error that ended the operation to its receiver
+    report: Box<dyn Fn(Error)>,
+    _handle: Box<dyn Any>,
+}
+
+impl ServiceDaemon {
+    /// Spawns the background thread of a new daemon.
+    pub fn new() -> Result<Self> {
+        let (commands, receiver) = mpsc::channel();
+        let stopped: Arc<(Mutex<bool>, Condvar)> = Arc::default();
+        let thread_stopped = stopped.clone();
+
+        let thread = thread::Builder::new()
+            .name("zeroconf-daemon".into())
+            .spawn(move || {
+                run(receiver);
+
+                let (lock, condvar) = &*thread_stopped;
+                *lock.lock().expect("should have been able to obtain lock") = true;
+                condvar.notify_all();
+            })
+            .map_err(|e| Error::ServiceError(format!("could not spawn daemon thread: {}", e)))?;
+
+        Ok(Self {
+            commands,
+            thread: Some(thread),
+            next_id: AtomicU64::new(0),
+            stopped,
+        })
+    }
+
+    /// Registers a service of the specified `ServiceType` on `port`, optionally under `name` and
+    /// with the specified TXT record entries.
+    ///
+    /// Returns a `DaemonOperation` receiving the outcome of the registration, through which the
+    /// service can be unregistered.
+    pub fn register(
+        &self,
+        service_type: ServiceType,
+        port: u16,
+        name: Option<String>,
+        txt: HashMap<String, String>,
+    ) -> Result<DaemonOperation<ServiceRegistration>> {
+        let (results, receiver) = mpsc::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        self.send(Command::Register {
+            id,
+            service_type,
+            port,
+            name,
+            txt,
+            results,
+        })?;
+
+        Ok(self.operation(id, receiver))
+    }
+
+    /// Browses for services of the specified `ServiceType`.
+    ///
+    /// Returns a `DaemonOperation` receiving the events of the browser, through which the browser
+    /// can be stopped.
+    pub fn browse(&self, service_type: ServiceType) -> Result<DaemonOperation<BrowserEvent>> {
+        let (events, receiver) = mpsc::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        self.send(Command::Browse {
+            id,
+            service_type,
+            events,
+        })?;
+
+        Ok(self.operation(id, receiver))
+    }
+
+    /// Stops all services and browsers and waits for the background thread to exit.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop()
+    }
+
+    /// Returns a `ShutdownToken` through which this daemon can be shut down from another thread.
+    pub fn shutdown_token(&self) -> ShutdownToken {
+        ShutdownToken {
+            commands: self.commands.clone(),
+            stopped: self.stopped.clone(),
+        }
+    }
+
+    fn send(&self, command: Command) -> Result<()> {
+        send(&self.commands, command)
+    }
+
+    fn operation<T>(&self, id: u64, receiver: Receiver<Result<T>>) -> DaemonOperation<T> {
+        DaemonOperation {
+            id,
+            commands: self.commands.clone(),
+            receiver,
+        }
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+
+        // the thread may have already exited, in which case there is nothing to stop
+        let _ = self.commands.send(Command::Shutdown);
+
+        thread
+            .join()
+            .map_err(|_| Error::Terminated("service daemon thread panicked".into()))
+    }
+}
+
+impl Drop for ServiceDaemon {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            warn!("{}", e);
+        }
+    }
+}
+
+impl fmt::Debug for ServiceDaemon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServiceDaemon")
+            .field("running", &self.thread.is_some())
+            .finish()
+    }
+}
+
+impl ShutdownToken {
+    /// Stops all services and browsers of the daemon, waiting up to `timeout` for them to be
+    /// stopped.
+    ///
+    /// Returns false if the daemon did not stop within `timeout`. Operations started on the
+    /// daemon afterwards fail with `Error::Terminated`.
+    pub fn shutdown(&self, timeout: Duration) -> bool {
+        // the thread may have already exited, in which case there is nothing to stop
+        let _ = self.commands.send(Command::Shutdown);
+
+        let (lock, condvar) = &*self.stopped;
+        let stopped = lock.lock().expect("should have been able to obtain lock");
+
+        let (stopped, _) = condvar
+            .wait_timeout_while(stopped, timeout, |stopped| !*stopped)
+            .expect("should have been able to obtain lock");
+
+        *stopped
+    }
+}
+
+impl fmt::Debug for ShutdownToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownToken").finish()
+    }
+}
+
+impl<T> DaemonOperation<T> {
+    /// Returns the `Receiver` of the results of this operation.
+    pub fn receiver(&self) -> &Receiver<Result<T>> {
+        &self.receiver
+    }
+
+    /// Stops this operation, unregistering the service or stopping the browser. Results already
+    /// delivered remain available from the receiver.
+    ///
+    /// Returns `Error::Terminated` if the daemon has shut down.
+    pub fn stop(&self) -> Result<()> {
+        send(&self.commands, Command::Stop(self.id))
+    }
+}
+
+impl<T> fmt::Debug for DaemonOperation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DaemonOperation")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+fn send(commands: &Sender<Command>, command: Command) -> Result<()> {
+    commands
+        .send(command)
+        .map_err(|_| Error::Terminated("service daemon has shut down".into()))
+}
+
+fn run(commands: Receiver<Command>) {
+    let mut operations: Vec<Operation> = Vec::new();
+
+    // blocks until the next command, handling every queued command in turn; ends once every
+    // handle on the daemon has been dropped
+    for command in commands.iter() {
+        // operations that ended on their own have nothing left to stop
+        operations.retain(|operation| !operation.thread.is_finished());
+
+        match command {
+            Command::Shutdown => break,
+            Command::Stop(id) => {
+                if let Some(index) = operations.iter().position(|operation| operation.id == id) {
+                    let operation = operations.swap_remove(index);
+                    operation.signal_stop();
+                    operation.join();
+                }
+            }
+            command => operations.extend(Operation::spawn(command)),
+        }
+    }
+
+    // stop every operation before waiting for any of them, so they unregister concurrently
+    for operation in &operations {
+        operation.signal_stop();
+    }
+
+    for operation in operations {
+        operation.join();
+    }
+}
+
+impl Operation {
+    /// Spawns the thread of the operation started by the specified command.
+    fn spawn(command: Command) -> Option<Self> {
+        let id = match &command {
+            Command::Register { id, .. } | Command::Browse { id, .. } => *id,
+            Command::Stop(_) | Command::Shutdown => return None,
+        };
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let waker: Arc<Mutex<Option<Waker>>> = Arc::default();
+        let thread_stopped = stopped.clone();
+        let thread_waker = waker.clone();
+
+        let thread = thread::Builder::new()
+            .name(format!("zeroconf-daemon-{}", id))
+            .spawn(move || {
+                if let Some(running) = start(command) {
+                    running.run(&thread_stopped, &thread_waker);
+                }
+            });
+
+        match thread {
+            Ok(thread) => Some(Self {
+                id,
+                stopped,
+                waker,
+                thread,
+            }),
+            Err(e) => {
+                // the command, and with it the sender of its results, has been dropped
+                warn!("could not spawn daemon operation thread: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Asks the operation to stop, waking its thread if it is waiting for events.
+    fn signal_stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        if let Some(waker) = &*self
+            .waker
+            .lock()
+            .expect("should have been able to obtain lock")
+        {
+            waker.wake();
+        }
+    }
+
+    /// Waits for the thread of the operation to exit, dropping its service or browser.
+    fn join(self) {
+        if self.thread.join().is_err() {
+            warn!("daemon operation thread panicked");
+        }
+    }
+}
+
+impl Running {
+    /// Polls the event loop until the operation is stopped or ends.
+    fn run(self, stopped: &AtomicBool, waker: &Mutex<Option<Waker>>) {
+        let event_loop_waker = self.event_loop.waker();
+
+        let timeout = match event_loop_waker {
+            Some(_) => WAKEABLE_POLL_TIMEOUT,
+            None => UNWAKEABLE_POLL_TIMEOUT,
+        };
+
+        // installed before `stopped` is first checked, so a stop is either seen here or wakes
+        // the poll below
+        *waker.lock().expect("should have been able to obtain lock") = event_loop_waker;
+
+        while !stopped.load(Ordering::SeqCst) {
+            // an operation that fails to poll is ended rather than retried, as the error would
+            // otherwise recur on every iteration
+            match self.event_loop.poll(timeout) {
+                Ok(()) => {}
+                Err(Error::Terminated(_)) => return,
+                Err(e) => {
+                    warn!("stopping daemon operation after error: {}", e);
+                    (self.report)(e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn start(command: Command) -> Option<Running> {
+    match command {
+        Command::Register {
+            service_type,
+            port,
+            name,
+            txt,
+            results,
+            ..
+        } => {
+            let mut service = MdnsService::new(service_type, port);
+
+            if let Some(name) = name {
+                service.set_name(&name);
+            }
+
+            if !txt.is_empty() {
+                service.set_txt_record(TxtRecord::from(txt));
+            }
+
+            let callback_results = results.clone();
+
+            service.set_registered_callback(Box::new(move |result, _| {
+                // the receiver may have been dropped, in which case the result is not of interest
+                let _ = callback_results.send(result);
+            }));
+
+            match service.register() {
+                Ok(event_loop) => Some(Running {
+                    event_loop,
+                    report: Box::new(move |e| {
+                        let _ = results.send(Err(e));
+                    }),
+                    _handle: Box::new(service),
+                }),
+                Err(e) => {
+                    let _ = results.send(Err(e));
+                    None
+                }
+            }
+        }
+        Command::Browse {
+            service_type,
+            events,
+            ..
+        } => {
+            let mut browser = MdnsBrowser::new(service_type);
+            let callback_events = events.clone();
+
+            browser.set_service_callback(Box::new(move |result, _| {
+                // the receiver may have been dropped, in which case the event is not of interest
+                let _ = callback_events.send(result);
+            }));
+
+            match browser.browse_services() {
+                Ok(event_loop) => Some(Running {
+                    event_loop,
+                    report: Box::new(move |e| {
+                        let _ = events.send(Err(e));
+                    }),
+                    _handle: Box::new(browser),
+                }),
+                Err(e) => {
+                    let _ = events.send(Err(e));
+                    None
+                }
+            }
+        }
+        Command::Stop(_) | Command::Shutdown => None,
+    }
+}
@@ -0,0 +1,108 @@
+//! Trait definition for cross-platform domain browser
+
+use crate::{EventLoop, NetworkInterface, Result};
+use std::any::Any;
+use std::sync::Arc;
+
+/// Event from [`MdnsDomainBrowser`] received by the `DomainBrowserCallback`.
+///
+/// [`MdnsDomainBrowser`]: type.MdnsDomainBrowser.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "event", content = "data", rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainEvent {
+    DomainAdded(Domain),
+    DomainRemoved(Domain),
+}
+
+/// The kind of domains listed by a [`MdnsDomainBrowser`].
+///
+/// [`MdnsDomainBrowser`]: type.MdnsDomainBrowser.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DomainKind {
+    /// Domains recommended for browsing for services
+    #[default]
+    Browse,
+    /// Domains recommended for registering services
+    Registration,
+}
+
+/// Represents a domain that has been found or removed by a [`MdnsDomainBrowser`].
+///
+/// [`MdnsDomainBrowser`]: type.MdnsDomainBrowser.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Getters, Builder, BuilderDelegate, Clone, PartialEq, Eq)]
+pub struct Domain {
+    /// The name of the domain, e.g. "local"
+    name: String,
+    /// The network interface the domain was found on
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    interface: NetworkInterface,
+}
+
+/// Interface for interacting with underlying mDNS implementation domain enumeration capabilities.
+pub trait TMdnsDomainBrowser {
+    /// Creates a new `MdnsDomainBrowser` that lists the domains of the specified `DomainKind`.
+    fn new(kind: DomainKind) -> Self;
+
+    /// Returns the kind of domains listed by this browser.
+    fn kind(&self) -> DomainKind;
+
+    /// Sets the network interface on which to list domains.
+    ///
+    /// Most applications will want to use the default value `NetworkInterface::Unspec` to list
+    /// domains on all available interfaces.
+    fn set_network_interface(&mut self, interface: NetworkInterface);
+
+    /// Returns the network interface on which to list domains.
+    fn network_interface(&self) -> NetworkInterface;
+
+    /// Sets the [`DomainBrowserCallback`] that is invoked when a domain has been added or
+    /// removed.
+    ///
+    /// [`DomainBrowserCallback`]: ../type.DomainBrowserCallback.html
+    fn set_domain_callback(&mut self, domain_callback: Box<DomainBrowserCallback>);
+
+    /// Sets the optional user context to pass through to the callback. This is useful if you need
+    /// to share state between pre and post-callback. The context type must implement `Any`.
+    fn set_context(&mut self, context: Box<dyn Any>);
+
+    /// Returns the optional user context to pass through to the callback.
+    fn context(&self) -> Option<&dyn Any>;
+
+    /// Starts listing domains. Returns an `EventLoop` which can be called to keep the browser
+    /// alive.
+    fn browse_domains(&mut self) -> Result<EventLoop>;
+}
+
+/// Callback invoked from [`MdnsDomainBrowser`] once a domain has been added or removed.
+///
+/// # Arguments
+/// * `domain_event` - The added or removed domain
+/// * `context` - The optional user context passed through
+///
+/// [`MdnsDomainBrowser`]: type.MdnsDomainBrowser.html
+pub type DomainBrowserCallback = dyn Fn(Result<DomainEvent>, Option<Arc<dyn Any>>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn domain_kind_defaults_to_browse() {
+        assert_eq!(DomainKind::default(), DomainKind::Browse);
+    }
+
+    #[test]
+    fn domain_interface_defaults_to_unspec() {
+        let domain = Domain::builder().name("local".into()).build().unwrap();
+
+        assert_eq!(*domain.interface(), NetworkInterface::Unspec);
+    }
+}
@@ -20,6 +20,24 @@ pub enum Error {
     /// An error occurred in an instance of an `crate::MdnsService`
     #[error("{0}")]
     ServiceError(String),
+    /// A service could not be registered because its name is already in use
+    #[error("Service name conflict: {0}")]
+    NameConflict(String),
+    /// The operation driven by an `crate::EventLoop` has terminated and can no longer be polled
+    #[error("Terminated: {0}")]
+    Terminated(String),
+    /// A `crate::TxtRecord` contains data in an unexpected format
+    #[error("Invalid TXT record: {0}")]
+    InvalidTxtRecord(String),
+    /// A `crate::TxtRecord` declares a `txtvers` payload version that is not supported
+    #[error("Unsupported TXT record version: {0}")]
+    UnsupportedVersion(u32),
+    /// A certificate presented by a service does not match the fingerprint it advertised
+    #[error("Certificate mismatch: {0}")]
+    CertificateMismatch(String),
+    /// A host name could not be resolved by any of the lookups consulted
+    #[error("Host not found: {0}")]
+    HostNotFound(String),
 }
 
 #[cfg(test)]
@@ -64,4 +82,40 @@ mod tests {
         let error = Error::ServiceError("uh oh spaghetti-o".into());
         assert_eq!(error.to_string(), "uh oh spaghetti-o");
     }
+
+    #[test]
+    fn test_name_conflict_display() {
+        let error = Error::NameConflict("my_service".into());
+        assert_eq!(error.to_string(), "Service name conflict: my_service");
+    }
+
+    #[test]
+    fn test_terminated_display() {
+        let error = Error::Terminated("uh oh spaghetti-o".into());
+        assert_eq!(error.to_string(), "Terminated: uh oh spaghetti-o");
+    }
+
+    #[test]
+    fn test_invalid_txt_record_display() {
+        let error = Error::InvalidTxtRecord("uh oh spaghetti-o".into());
+        assert_eq!(error.to_string(), "Invalid TXT record: uh oh spaghetti-o");
+    }
+
+    #[test]
+    fn test_unsupported_version_display() {
+        let error = Error::UnsupportedVersion(3);
+        assert_eq!(error.to_string(), "Unsupported TXT record version: 3");
+    }
+
+    #[test]
+    fn test_certificate_mismatch_display() {
+        let error = Error::CertificateMismatch("uh oh spaghetti-o".into());
+        assert_eq!(error.to_string(), "Certificate mismatch: uh oh spaghetti-o");
+    }
+
+    #[test]
+    fn test_host_not_found_display() {
+        let error = Error::HostNotFound("uh oh spaghetti-o".into());
+        assert_eq!(error.to_string(), "Host not found: uh oh spaghetti-o");
+    }
 }
@@ -1,11 +1,224 @@
 //! Trait definition for cross-platform event loop
 
-use crate::Result;
-use std::time::Duration;
+use crate::{Error, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// A handle on the underlying implementation to poll the event loop. Typically, `poll()`
 /// is called in a loop to keep a `MdnsService` or `MdnsBrowser` running.
+///
+/// On unix targets using Bonjour, the event loop also implements `AsRawFd`, so that its socket can
+/// be registered with an external reactor and `poll()` only called once it is readable. The Avahi
+/// event loop on Linux does not: `AvahiSimplePoll` watches a changing set of descriptors and
+/// timeouts internally, so it must be polled on a timer instead.
 pub trait TEventLoop {
     /// Polls for new events.
+    ///
+    /// Returns `Error::Terminated` once the operation driven by this event loop has terminated.
     fn poll(&self, timeout: Duration) -> Result<()>;
+
+    /// Polls repeatedly until at least one event has been dispatched to a callback or `deadline`
+    /// has passed, whichever comes first. Returns the number of events dispatched.
+    ///
+    /// Returns `Error::Terminated` once the operation driven by this event loop has terminated.
+    fn poll_until(&self, deadline: Instant) -> Result<usize> {
+        let start = self.dispatch_count();
+
+        loop {
+            let dispatched = self.dispatch_count() - start;
+            let now = Instant::now();
+
+            if dispatched > 0 || now >= deadline {
+                return Ok(dispatched);
+            }
+
+            self.poll(deadline - now)?;
+        }
+    }
+
+    /// Returns the total number of events this event loop has dispatched to callbacks.
+    fn dispatch_count(&self) -> usize;
+
+    /// Sets the duration after which a callback invoked by this event loop is logged as slow,
+    /// or `None` to disable the warning. Defaults to [`DEFAULT_SLOW_CALLBACK_THRESHOLD`].
+    ///
+    /// Callbacks run on the polling thread, so a slow callback delays the processing of further
+    /// events.
+    ///
+    /// [`DEFAULT_SLOW_CALLBACK_THRESHOLD`]: constant.DEFAULT_SLOW_CALLBACK_THRESHOLD.html
+    fn set_slow_callback_threshold(&self, threshold: Option<Duration>);
+
+    /// Returns the reason the operation driven by this event loop terminated, or `None` if it is
+    /// still running.
+    fn last_error(&self) -> Option<String>;
+
+    /// Returns the time elapsed since this event loop was created.
+    fn uptime(&self) -> Duration;
+
+    /// Returns true if the operation driven by this event loop has not terminated.
+    fn is_running(&self) -> bool {
+        self.last_error().is_none()
+    }
+}
+
+/// Shared record of whether the operation driven by an event loop has terminated.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Termination(Arc<Mutex<Option<String>>>);
+
+impl Termination {
+    /// Marks the operation as terminated for the specified `reason`.
+    pub fn terminate(&self, reason: String) {
+        *self
+            .0
+            .lock()
+            .expect("should have been able to obtain lock on termination") = Some(reason);
+    }
+
+    /// Returns the reason the operation terminated, if it has.
+    pub fn reason(&self) -> Option<String> {
+        self.0
+            .lock()
+            .expect("should have been able to obtain lock on termination")
+            .clone()
+    }
+
+    /// Returns `Error::Terminated` if the operation has terminated.
+    pub fn check(&self) -> Result<()> {
+        match self.reason() {
+            Some(reason) => Err(Error::Terminated(reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Default duration after which a callback is reported as slow.
+pub const DEFAULT_SLOW_CALLBACK_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// Shared state for invoking the callbacks of an event loop, counting the events dispatched and
+/// timing the callbacks.
+#[derive(Debug, Clone)]
+pub(crate) struct Dispatcher(Arc<DispatcherState>);
+
+#[derive(Debug)]
+struct DispatcherState {
+    count: AtomicUsize,
+    slow_threshold: Mutex<Option<Duration>>,
+}
+
+impl Dispatcher {
+    /// Invokes the specified callback, logging a warning if it exceeds the slow callback
+    /// threshold.
+    pub fn dispatch<F: FnOnce()>(&self, callback: F) {
+        self.0.count.fetch_add(1, Ordering::Relaxed);
+
+        let start = Instant::now();
+        callback();
+        let elapsed = start.elapsed();
+
+        if let Some(threshold) = self.slow_threshold() {
+            if elapsed > threshold {
+                warn!(
+                    "callback took {:?}, exceeding the threshold of {:?}; slow callbacks delay \
+                     the event loop and may cause events to be missed",
+                    elapsed, threshold
+                );
+            }
+        }
+    }
+
+    /// Returns the number of events dispatched so far.
+    pub fn count(&self) -> usize {
+        self.0.count.load(Ordering::Relaxed)
+    }
+
+    /// Sets the duration after which a callback is reported as slow, or `None` to disable.
+    pub fn set_slow_threshold(&self, threshold: Option<Duration>) {
+        *self.lock_slow_threshold() = threshold;
+    }
+
+    /// Returns the duration after which a callback is reported as slow.
+    pub fn slow_threshold(&self) -> Option<Duration> {
+        *self.lock_slow_threshold()
+    }
+
+    fn lock_slow_threshold(&self) -> std::sync::MutexGuard<'_, Option<Duration>> {
+        self.0
+            .slow_threshold
+            .lock()
+            .expect("should have been able to obtain lock on slow callback threshold")
+    }
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self(Arc::new(DispatcherState {
+            count: AtomicUsize::new(0),
+            slow_threshold: Mutex::new(Some(DEFAULT_SLOW_CALLBACK_THRESHOLD)),
+        }))
+    }
+}
+
+/// Wakes an event loop that is blocked in `poll()` from another thread.
+pub(crate) struct Waker(Box<dyn Fn() + Send>);
+
+impl Waker {
+    pub fn new(wake: impl Fn() + Send + 'static) -> Self {
+        Self(Box::new(wake))
+    }
+
+    /// Makes the current or next `poll()` of the event loop return without waiting for its
+    /// timeout.
+    pub fn wake(&self) {
+        (self.0)()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn termination_check_is_ok_by_default() {
+        assert_eq!(Termination::default().check(), Ok(()));
+    }
+
+    #[test]
+    fn dispatcher_count_is_shared_between_clones() {
+        let dispatcher = Dispatcher::default();
+        let mut invoked = 0;
+
+        dispatcher.clone().dispatch(|| invoked += 1);
+        dispatcher.dispatch(|| invoked += 1);
+
+        assert_eq!(invoked, 2);
+        assert_eq!(dispatcher.count(), 2);
+    }
+
+    #[test]
+    fn dispatcher_slow_threshold() {
+        let dispatcher = Dispatcher::default();
+        assert_eq!(
+            dispatcher.slow_threshold(),
+            Some(DEFAULT_SLOW_CALLBACK_THRESHOLD)
+        );
+
+        dispatcher.clone().set_slow_threshold(None);
+        assert_eq!(dispatcher.slow_threshold(), None);
+    }
+
+    #[test]
+    fn termination_reason_is_none_by_default() {
+        assert_eq!(Termination::default().reason(), None);
+    }
+
+    #[test]
+    fn termination_check_returns_reason() {
+        let termination = Termination::default();
+        termination.clone().terminate("uh oh spaghetti-o".into());
+        assert_eq!(
+            termination.check(),
+            Err(Error::Terminated("uh oh spaghetti-o".into()))
+        );
+    }
 }
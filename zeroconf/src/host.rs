@@ -0,0 +1,126 @@
+//! Host name resolution over mDNS with fallback to the system resolver
+
+use crate::{Error, Result};
+use std::net::{IpAddr, ToSocketAddrs};
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+use crate::avahi::host_resolver::resolve_host_name;
+#[cfg(any(target_vendor = "apple", target_vendor = "pc"))]
+use crate::bonjour::host_resolver::resolve_host_name;
+
+/// A source of host name resolution consulted by [`resolve_host_with()`].
+///
+/// [`resolve_host_with()`]: fn.resolve_host_with.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HostLookup {
+    /// Multicast DNS, through the platform's mDNS implementation. Only consulted for names in the
+    /// `local` domain.
+    Mdns,
+    /// The system resolver (`getaddrinfo`), which also handles address literals.
+    System,
+}
+
+/// Time an mDNS lookup keeps waiting for the addresses of one family once those of the other have
+/// been resolved, as hosts without addresses of that family never answer for it.
+pub(crate) const OTHER_FAMILY_GRACE: Duration = Duration::from_millis(500);
+
+/// The order in which [`resolve_host_any()`] consults lookups.
+///
+/// [`resolve_host_any()`]: fn.resolve_host_any.html
+pub const DEFAULT_HOST_LOOKUP_ORDER: [HostLookup; 2] = [HostLookup::Mdns, HostLookup::System];
+
+/// Resolves `host_name` to its addresses, trying mDNS first and falling back to the system
+/// resolver, so that `.local` and unicast names can be resolved uniformly.
+///
+/// `timeout` bounds the mDNS lookup; the system resolver is bound by its own configuration.
+pub fn resolve_host_any(host_name: &str, timeout: Duration) -> Result<Vec<IpAddr>> {
+    resolve_host_with(host_name, &DEFAULT_HOST_LOOKUP_ORDER, timeout)
+}
+
+/// Resolves `host_name` to its addresses, consulting the specified lookups in order and returning
+/// the addresses of the first one that succeeds.
+///
+/// Returns the error of the last lookup consulted, or `Error::HostNotFound` if none was.
+pub fn resolve_host_with(
+    host_name: &str,
+    order: &[HostLookup],
+    timeout: Duration,
+) -> Result<Vec<IpAddr>> {
+    let mut error = Error::HostNotFound(format!("no lookup was consulted for `{}`", host_name));
+
+    for lookup in order {
+        let result = match lookup {
+            HostLookup::Mdns if is_mdns_host_name(host_name) => {
+                resolve_host_name(host_name, timeout)
+            }
+            HostLookup::Mdns => continue,
+            HostLookup::System => resolve_system(host_name),
+        };
+
+        match result {
+            Ok(addresses) => return Ok(addresses),
+            Err(e) => {
+                debug!("{:?} lookup of `{}` failed: {}", lookup, host_name, e);
+                error = e;
+            }
+        }
+    }
+
+    Err(error)
+}
+
+fn is_mdns_host_name(host_name: &str) -> bool {
+    let host_name = host_name.trim_end_matches('.').to_ascii_lowercase();
+    host_name == "local" || host_name.ends_with(".local")
+}
+
+fn resolve_system(host_name: &str) -> Result<Vec<IpAddr>> {
+    let mut addresses: Vec<IpAddr> = Vec::new();
+
+    let resolved = (host_name.trim_end_matches('.'), 0)
+        .to_socket_addrs()
+        .map_err(|e| Error::HostNotFound(format!("`{}`: {}", host_name, e)))?;
+
+    for address in resolved {
+        if !addresses.contains(&address.ip()) {
+            addresses.push(address.ip());
+        }
+    }
+
+    if addresses.is_empty() {
+        Err(Error::HostNotFound(format!(
+            "`{}` has no addresses",
+            host_name
+        )))
+    } else {
+        Ok(addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_mdns_host_name_matches_local_domain() {
+        assert!(is_mdns_host_name("printer.local"));
+        assert!(is_mdns_host_name("Printer.LOCAL."));
+        assert!(!is_mdns_host_name("printer.example.com"));
+        assert!(!is_mdns_host_name("localhost"));
+    }
+
+    #[test]
+    fn resolve_host_with_falls_back_to_system() {
+        let addresses = resolve_host_with("127.0.0.1", &DEFAULT_HOST_LOOKUP_ORDER, Duration::ZERO);
+        assert_eq!(addresses.unwrap(), vec![IpAddr::from([127, 0, 0, 1])]);
+    }
+
+    #[test]
+    fn resolve_host_with_no_lookups_fails() {
+        assert!(matches!(
+            resolve_host_with("127.0.0.1", &[], Duration::ZERO),
+            Err(Error::HostNotFound(_))
+        ));
+    }
+}
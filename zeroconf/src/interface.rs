@@ -1,8 +1,59 @@
 /// Represents a network interface for mDNS services
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub enum NetworkInterface {
     /// No interface specified, bind to all available interfaces
+    #[default]
     Unspec,
     /// An interface at a specified index
     AtIndex(u32),
 }
+
+/// Represents the IP protocol over which an mDNS result was received
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IpProtocol {
+    /// No protocol reported; Bonjour reports one result per interface for both protocols
+    #[default]
+    Unspec,
+    /// Received over IPv4
+    V4,
+    /// Received over IPv6
+    V6,
+}
+
+impl NetworkInterface {
+    /// Returns the friendly name of the adapter at this interface (e.g. "Wi-Fi" or "Ethernet 2"),
+    /// as shown to Windows users, or `None` for `NetworkInterface::Unspec` or if no adapter has
+    /// this index.
+    #[cfg(target_vendor = "pc")]
+    pub fn friendly_name(&self) -> Option<String> {
+        let index = match self {
+            NetworkInterface::Unspec => return None,
+            NetworkInterface::AtIndex(index) => *index,
+        };
+
+        adapter_friendly_names()
+            .into_iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, name)| name)
+    }
+
+    /// Returns the interface of the adapter with the specified friendly name (e.g. "Wi-Fi"),
+    /// compared case-insensitively, or `None` if there is no such adapter.
+    #[cfg(target_vendor = "pc")]
+    pub fn from_friendly_name(name: &str) -> Option<Self> {
+        adapter_friendly_names()
+            .into_iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(index, _)| NetworkInterface::AtIndex(index))
+    }
+}
+
+#[cfg(target_vendor = "pc")]
+fn adapter_friendly_names() -> Vec<(u32, String)> {
+    crate::bonjour::bonjour_util::adapter_friendly_names().unwrap_or_else(|e| {
+        warn!("could not list network adapters: {}", e);
+        Vec::new()
+    })
+}
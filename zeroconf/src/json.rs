@@ -0,0 +1,99 @@
+//! JSON Lines output of browser and registration events
+
+use crate::{ServiceBrowserCallback, ServiceRegisteredCallback};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::io::{self, Write};
+
+/// Writes the specified value to `writer` as a single line of JSON and flushes it.
+pub fn write_json_line<W: Write, T: Serialize>(writer: &mut W, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+/// Returns a [`ServiceBrowserCallback`] that writes every [`BrowserEvent`] to `writer` as JSON
+/// Lines. Errors reported by the browser or encountered while writing are logged.
+///
+/// [`ServiceBrowserCallback`]: ../type.ServiceBrowserCallback.html
+/// [`BrowserEvent`]: ../enum.BrowserEvent.html
+pub fn json_lines_browser_callback<W: Write + 'static>(writer: W) -> Box<ServiceBrowserCallback> {
+    let writer = RefCell::new(writer);
+
+    Box::new(move |result, _| match result {
+        Ok(event) => {
+            if let Err(e) = write_json_line(&mut *writer.borrow_mut(), &event) {
+                warn!("could not write browser event: {}", e);
+            }
+        }
+        Err(e) => warn!("browser error: {}", e),
+    })
+}
+
+/// Returns a [`ServiceRegisteredCallback`] that writes every [`ServiceRegistration`] to `writer`
+/// as JSON Lines. Errors reported by the service or encountered while writing are logged.
+///
+/// [`ServiceRegisteredCallback`]: ../type.ServiceRegisteredCallback.html
+/// [`ServiceRegistration`]: ../struct.ServiceRegistration.html
+pub fn json_lines_registered_callback<W: Write + 'static>(
+    writer: W,
+) -> Box<ServiceRegisteredCallback> {
+    let writer = RefCell::new(writer);
+
+    Box::new(move |result, _| match result {
+        Ok(registration) => {
+            if let Err(e) = write_json_line(&mut *writer.borrow_mut(), &registration) {
+                warn!("could not write service registration: {}", e);
+            }
+        }
+        Err(e) => warn!("service error: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::{BrowserEvent, ServiceRemoval};
+
+    fn to_json_line<T: Serialize>(value: &T) -> String {
+        let mut out = Vec::new();
+        write_json_line(&mut out, value).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn write_json_line_tags_events() {
+        assert_eq!(
+            to_json_line(&BrowserEvent::Stopped),
+            "{\"event\":\"stopped\"}\n"
+        );
+    }
+
+    #[test]
+    fn write_json_line_tags_status_events() {
+        assert_eq!(
+            to_json_line(&BrowserEvent::AllForNow),
+            "{\"event\":\"all_for_now\"}\n"
+        );
+        assert_eq!(
+            to_json_line(&BrowserEvent::CacheExhausted),
+            "{\"event\":\"cache_exhausted\"}\n"
+        );
+    }
+
+    #[test]
+    fn write_json_line_includes_event_data() {
+        let removal = ServiceRemoval::builder()
+            .name("foo".into())
+            .kind("_http._tcp".into())
+            .domain("local".into())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            to_json_line(&BrowserEvent::Remove(removal)),
+            "{\"event\":\"remove\",\"data\":{\"name\":\"foo\",\"kind\":\"_http._tcp\",\"domain\":\"local\",\"interface\":\"Unspec\",\"protocol\":\"Unspec\"}}\n"
+        );
+    }
+}
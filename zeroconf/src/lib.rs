@@ -217,22 +217,51 @@ mod service_type;
 mod tests;
 
 pub mod browser;
+pub mod claim;
+pub mod daemon;
+pub mod domain;
 pub mod error;
 pub mod event_loop;
+pub mod host;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod pool;
 pub mod prelude;
+pub mod resolver;
 pub mod service;
+#[cfg(all(unix, feature = "signals"))]
+pub mod signals;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "txt-compression")]
+pub mod txt_codec;
 pub mod txt_record;
+pub mod txt_schema;
+#[cfg(feature = "txt-signing")]
+pub mod txt_signing;
 
 #[cfg(target_os = "linux")]
 pub mod avahi;
 #[cfg(any(target_vendor = "apple", target_vendor = "pc"))]
 pub mod bonjour;
 
-pub use browser::{BrowserEvent, ServiceBrowserCallback, ServiceDiscovery, ServiceRemoval};
+pub use browser::{
+    BrowserEvent, DuplicateName, ServiceAddress, ServiceBrowserCallback, ServiceDiscovery,
+    ServiceInstance, ServiceRemoval, TXT_PEER_ID_KEY,
+};
+pub use claim::ServiceClaim;
+pub use daemon::{DaemonOperation, ServiceDaemon, ShutdownToken};
+pub use domain::{Domain, DomainBrowserCallback, DomainEvent, DomainKind};
 pub use error::Error;
+pub use host::{resolve_host_any, resolve_host_with, HostLookup};
 pub use interface::*;
+pub use pool::{DiscoveredPool, PoolKey};
+pub use resolver::{ServiceKey, ServiceResolvedCallback};
 pub use service::{ServiceRegisteredCallback, ServiceRegistration};
 pub use service_type::*;
+pub use txt_schema::{TxtSchema, TxtSchemaRegistry};
 
 /// Type alias for the platform-specific mDNS browser implementation
 #[cfg(target_os = "linux")]
@@ -241,6 +270,20 @@ pub type MdnsBrowser = avahi::browser::AvahiMdnsBrowser;
 #[cfg(any(target_vendor = "apple", target_vendor = "pc"))]
 pub type MdnsBrowser = bonjour::browser::BonjourMdnsBrowser;
 
+/// Type alias for the platform-specific mDNS domain browser implementation
+#[cfg(target_os = "linux")]
+pub type MdnsDomainBrowser = avahi::domain_browser::AvahiMdnsDomainBrowser;
+/// Type alias for the platform-specific mDNS domain browser implementation
+#[cfg(any(target_vendor = "apple", target_vendor = "pc"))]
+pub type MdnsDomainBrowser = bonjour::domain_browser::BonjourMdnsDomainBrowser;
+
+/// Type alias for the platform-specific mDNS resolver implementation
+#[cfg(target_os = "linux")]
+pub type MdnsResolver = avahi::service_resolver::AvahiMdnsResolver;
+/// Type alias for the platform-specific mDNS resolver implementation
+#[cfg(any(target_vendor = "apple", target_vendor = "pc"))]
+pub type MdnsResolver = bonjour::service_resolver::BonjourMdnsResolver;
+
 /// Type alias for the platform-specific mDNS service implementation
 #[cfg(target_os = "linux")]
 pub type MdnsService = avahi::service::AvahiMdnsService;
@@ -0,0 +1,115 @@
+//! Helpers for connecting to discovered services
+
+use crate::{NetworkInterface, ServiceDiscovery};
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+impl ServiceDiscovery {
+    /// Opens a TCP connection to this service through the network interface it was resolved on,
+    /// rather than the interface chosen by the default route.
+    ///
+    /// The socket is bound with `SO_BINDTODEVICE` on Linux, which may require `CAP_NET_RAW`, and
+    /// `IP_BOUND_IF`/`IPV6_BOUND_IF` on Apple platforms. If the interface is unknown, the
+    /// connection is made without binding. Other platforms return `ErrorKind::Unsupported`.
+    pub fn connect_via_same_interface(&self, timeout: Option<Duration>) -> io::Result<TcpStream> {
+        let address = self
+            .socket_addr()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let socket = Socket::new(
+            Domain::for_address(address),
+            Type::STREAM,
+            Some(Protocol::TCP),
+        )?;
+
+        if let NetworkInterface::AtIndex(index) = *self.interface() {
+            bind_to_interface(&socket, &address, index)?;
+        }
+
+        match timeout {
+            Some(timeout) => socket.connect_timeout(&SockAddr::from(address), timeout)?,
+            None => socket.connect(&SockAddr::from(address))?,
+        }
+
+        Ok(socket.into())
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+fn bind_to_interface(socket: &Socket, _address: &SocketAddr, index: u32) -> io::Result<()> {
+    let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+
+    if unsafe { libc::if_indextoname(index, name.as_mut_ptr()) }.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+
+    let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+    socket.bind_device(Some(name.to_bytes()))
+}
+
+#[cfg(target_vendor = "apple")]
+fn bind_to_interface(socket: &Socket, address: &SocketAddr, index: u32) -> io::Result<()> {
+    let index = std::num::NonZeroU32::new(index);
+
+    match address {
+        SocketAddr::V4(_) => socket.bind_device_by_index_v4(index),
+        SocketAddr::V6(_) => socket.bind_device_by_index_v6(index),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "fuchsia",
+    target_os = "linux",
+    target_vendor = "apple"
+)))]
+fn bind_to_interface(_socket: &Socket, _address: &SocketAddr, _index: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "binding to an interface is not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::ServiceType;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn connect_via_same_interface_without_interface() {
+        crate::tests::setup();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let discovery = ServiceDiscovery::builder()
+            .name("foo".into())
+            .service_type(ServiceType::new("http", "tcp").unwrap())
+            .domain("local".into())
+            .host_name("foo.local".into())
+            .address("127.0.0.1".into())
+            .port(listener.local_addr().unwrap().port())
+            .txt(None)
+            .build()
+            .unwrap();
+
+        let mut stream = discovery
+            .connect_via_same_interface(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        stream.write_all(b"ping").unwrap();
+
+        let mut received = [0; 4];
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_exact(&mut received)
+            .unwrap();
+        assert_eq!(&received, b"ping");
+    }
+}
@@ -0,0 +1,255 @@
+//! Connections to every live instance of a service type
+
+use crate::browser::Resolutions;
+use crate::prelude::*;
+use crate::{
+    BrowserEvent, EventLoop, IpProtocol, MdnsBrowser, NetworkInterface, Result, ServiceDiscovery,
+    ServiceRemoval, ServiceType,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Callback that opens a connection of type `T` to a newly discovered service instance.
+pub type PoolConnector<T> = dyn Fn(&ServiceDiscovery) -> Result<T>;
+
+/// Identifies a service instance in a [`DiscoveredPool`].
+///
+/// [`DiscoveredPool`]: struct.DiscoveredPool.html
+#[derive(Debug, Getters, Clone, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    /// The "abc" part in "abc._http._udp.local"
+    name: String,
+    /// The "_http._udp" part in "abc._http._udp.local"
+    kind: String,
+    /// The "local" part in "abc._http._udp.local"
+    domain: String,
+}
+
+impl PoolKey {
+    fn from_discovery(service: &ServiceDiscovery) -> Self {
+        let service_type = service.service_type();
+
+        Self {
+            name: service.name().clone(),
+            kind: format!("_{}._{}", service_type.name(), service_type.protocol()),
+            domain: service.domain().clone(),
+        }
+    }
+
+    fn from_removal(removal: &ServiceRemoval) -> Self {
+        // removals of sub-type browses are reported under the sub-type, e.g.
+        // "_printer._sub._http._tcp", while discoveries carry only the service type
+        let labels = removal.kind().rsplitn(3, '.').collect::<Vec<_>>();
+
+        let kind = match labels[..] {
+            [protocol, name, ..] => format!("{}.{}", name, protocol),
+            _ => removal.kind().clone(),
+        };
+
+        Self {
+            name: removal.name().clone(),
+            kind,
+            domain: removal.domain().clone(),
+        }
+    }
+}
+
+struct PoolEntry<T> {
+    connection: T,
+    /// The interface and protocol of the resolution the connection was opened to
+    resolution: (NetworkInterface, IpProtocol),
+}
+
+impl<T> PoolEntry<T> {
+    fn connect(connector: &PoolConnector<T>, service: &ServiceDiscovery) -> Option<Self> {
+        match connector(service) {
+            Ok(connection) => Some(Self {
+                connection,
+                resolution: (*service.interface(), *service.protocol()),
+            }),
+            Err(e) => {
+                warn!("could not connect to `{}`: {}", service.name(), e);
+                None
+            }
+        }
+    }
+}
+
+/// Pool state shared with the browse callback.
+struct PoolState<T> {
+    connections: HashMap<PoolKey, PoolEntry<T>>,
+    resolutions: Resolutions<PoolKey>,
+}
+
+impl<T> Default for PoolState<T> {
+    fn default() -> Self {
+        Self {
+            connections: HashMap::new(),
+            resolutions: Resolutions::default(),
+        }
+    }
+}
+
+/// Maintains a connection to every live instance of a service type, keyed by instance name, type
+/// and domain.
+///
+/// A connection is opened with the user-supplied [`PoolConnector`] when an instance is first
+/// discovered and dropped once it has been removed from every interface and protocol it was
+/// resolved on. If the resolution a connection was opened to is removed while the instance is
+/// still resolved elsewhere (e.g. on a multi-homed host losing one of its networks), the pool
+/// rebalances by reconnecting to one of the remaining resolutions. Browse events are only
+/// processed while [`poll()`] is being called.
+///
+/// [`PoolConnector`]: type.PoolConnector.html
+/// [`poll()`]: #method.poll
+pub struct DiscoveredPool<T> {
+    event_loop: EventLoop,
+    _browser: MdnsBrowser,
+    state: Rc<RefCell<PoolState<T>>>,
+}
+
+impl<T: 'static> DiscoveredPool<T> {
+    /// Starts browsing for the specified `ServiceType`, opening connections with `connector` as
+    /// instances are discovered.
+    ///
+    /// Instances that `connector` fails to connect to are logged and left out of the pool until
+    /// they are announced again.
+    pub fn new(service_type: ServiceType, connector: Box<PoolConnector<T>>) -> Result<Self> {
+        let mut browser = MdnsBrowser::new(service_type);
+        let state: Rc<RefCell<PoolState<T>>> = Rc::default();
+        let callback_state = state.clone();
+
+        browser.set_service_callback(Box::new(move |result, _| match result {
+            Ok(BrowserEvent::Add(service)) => {
+                let mut state = callback_state.borrow_mut();
+                let key = PoolKey::from_discovery(&service);
+
+                state.resolutions.add(key.clone(), &service);
+
+                if state.connections.contains_key(&key) {
+                    return;
+                }
+
+                if let Some(entry) = PoolEntry::connect(connector.as_ref(), &service) {
+                    state.connections.insert(key, entry);
+                }
+            }
+            Ok(BrowserEvent::Remove(removal)) => {
+                let mut state = callback_state.borrow_mut();
+                let key = PoolKey::from_removal(&removal);
+
+                // removals of interfaces and protocols the instance never resolved on are ignored
+                if !state.resolutions.remove(&key, &removal) {
+                    return;
+                }
+
+                let remaining = state.resolutions.get(&key).cloned();
+
+                match remaining {
+                    None => {
+                        state.connections.remove(&key);
+                    }
+                    Some(service) => {
+                        let removed = (*removal.interface(), *removal.protocol());
+
+                        let connected_to_removed = matches!(
+                            state.connections.get(&key),
+                            Some(entry) if entry.resolution == removed
+                        );
+
+                        if connected_to_removed {
+                            state.connections.remove(&key);
+
+                            if let Some(entry) = PoolEntry::connect(connector.as_ref(), &service) {
+                                state.connections.insert(key, entry);
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("discovery error: {}", e),
+        }));
+
+        let event_loop = browser.browse_services()?;
+
+        Ok(Self {
+            event_loop,
+            _browser: browser,
+            state,
+        })
+    }
+}
+
+impl<T> DiscoveredPool<T> {
+    /// Polls the underlying event loop, updating the pool with any browse events received.
+    pub fn poll(&self, timeout: Duration) -> Result<()> {
+        self.event_loop.poll(timeout)
+    }
+
+    /// Returns the number of connections in the pool.
+    pub fn len(&self) -> usize {
+        self.state.borrow().connections.len()
+    }
+
+    /// Returns true if the pool contains no connections.
+    pub fn is_empty(&self) -> bool {
+        self.state.borrow().connections.is_empty()
+    }
+
+    /// Returns the keys of the instances currently in the pool.
+    pub fn keys(&self) -> Vec<PoolKey> {
+        self.state.borrow().connections.keys().cloned().collect()
+    }
+
+    /// Calls `f` with the connection to the specified instance, returning its result, or `None`
+    /// if the instance is not in the pool.
+    ///
+    /// # Panics
+    /// Panics if `f` polls or otherwise accesses this pool.
+    pub fn with_connection<R>(&self, key: &PoolKey, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.state
+            .borrow_mut()
+            .connections
+            .get_mut(key)
+            .map(|entry| f(&mut entry.connection))
+    }
+
+    /// Calls `f` with every connection in the pool.
+    ///
+    /// # Panics
+    /// Panics if `f` polls or otherwise accesses this pool.
+    pub fn for_each_connection(&self, mut f: impl FnMut(&PoolKey, &mut T)) {
+        for (key, entry) in self.state.borrow_mut().connections.iter_mut() {
+            f(key, &mut entry.connection);
+        }
+    }
+}
+
+impl<T> fmt::Debug for DiscoveredPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscoveredPool")
+            .field("instances", &self.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_key_from_removal_strips_sub_type() {
+        let removal = ServiceRemoval::builder()
+            .name("foo".into())
+            .kind("_printer._sub._http._tcp".into())
+            .domain("local".into())
+            .build()
+            .unwrap();
+
+        assert_eq!(PoolKey::from_removal(&removal).kind(), "_http._tcp");
+    }
+}
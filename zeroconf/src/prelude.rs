@@ -1,9 +1,15 @@
 //! Crate prelude
 
 pub use crate::browser::TMdnsBrowser;
+pub use crate::domain::TMdnsDomainBrowser;
 pub use crate::event_loop::TEventLoop;
+pub use crate::resolver::TMdnsResolver;
 pub use crate::service::TMdnsService;
+#[cfg(feature = "txt-compression")]
+pub use crate::txt_codec::TCompressedTxtRecord;
 pub use crate::txt_record::TTxtRecord;
+#[cfg(feature = "txt-signing")]
+pub use crate::txt_signing::TSignedTxtRecord;
 
 /// Implements a `builder()` function for the specified type
 pub trait BuilderDelegate<T: Default> {
@@ -0,0 +1,249 @@
+//! Trait definition for cross-platform resolver
+
+use crate::{Error, EventLoop, NetworkInterface, Result, ServiceDiscovery, ServiceType};
+use std::any::Any;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Identifies a service instance to resolve with [`TMdnsResolver::resolve_many()`].
+///
+/// [`TMdnsResolver::resolve_many()`]: trait.TMdnsResolver.html#method.resolve_many
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Getters, Builder, BuilderDelegate, Clone, PartialEq, Eq)]
+pub struct ServiceKey {
+    /// The name the service is registered under
+    name: String,
+    /// The type of the service
+    service_type: ServiceType,
+    /// The domain of the service, or `None` for the default domain
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    domain: Option<String>,
+}
+
+impl From<&ServiceDiscovery> for ServiceKey {
+    fn from(service: &ServiceDiscovery) -> Self {
+        Self {
+            name: service.name().clone(),
+            service_type: service.service_type().clone(),
+            domain: Some(service.domain().clone()),
+        }
+    }
+}
+
+/// Interface for interacting with underlying mDNS implementation capabilities for resolving a
+/// known service instance without browsing for it.
+pub trait TMdnsResolver {
+    /// Creates a new `MdnsResolver` that resolves the service instance with the specified `name`
+    /// and `ServiceType` (e.g. `_http._tcp`).
+    fn new(service_type: ServiceType, name: &str) -> Self;
+
+    /// Sets the network interface on which to resolve the service.
+    ///
+    /// Most applications will want to use the default value `NetworkInterface::Unspec` to resolve
+    /// on all available interfaces.
+    fn set_network_interface(&mut self, interface: NetworkInterface);
+
+    /// Returns the network interface on which to resolve the service.
+    fn network_interface(&self) -> NetworkInterface;
+
+    /// Sets the domain of the service to resolve.
+    ///
+    /// Most applications will want to use the default domain.
+    fn set_domain(&mut self, domain: &str);
+
+    /// Returns the domain of the service to resolve, or `None` for the default domain.
+    fn domain(&self) -> Option<&str>;
+
+    /// Sets the [`ServiceResolvedCallback`] that is invoked when the service has been resolved.
+    ///
+    /// [`ServiceResolvedCallback`]: ../type.ServiceResolvedCallback.html
+    fn set_resolved_callback(&mut self, resolved_callback: Box<ServiceResolvedCallback>);
+
+    /// Returns a `Receiver` that resolution results are sent to instead of a
+    /// [`ServiceResolvedCallback`], replacing any callback previously set.
+    ///
+    /// Results are still only produced while the `EventLoop` is being polled.
+    ///
+    /// [`ServiceResolvedCallback`]: ../type.ServiceResolvedCallback.html
+    fn resolved_channel(&mut self) -> Receiver<Result<ServiceDiscovery>> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.set_resolved_callback(Box::new(move |result, _| {
+            // the receiver may have been dropped, in which case the result is not of interest
+            let _ = sender.send(result);
+        }));
+
+        receiver
+    }
+
+    /// Sets the optional user context to pass through to the callback. This is useful if you need
+    /// to share state between pre and post-callback. The context type must implement `Any`.
+    fn set_context(&mut self, context: Box<dyn Any>);
+
+    /// Returns the optional user context to pass through to the callback.
+    fn context(&self) -> Option<&dyn Any>;
+
+    /// Starts resolving the service. Returns an `EventLoop` which can be called to keep the
+    /// resolver alive.
+    fn resolve(&mut self) -> Result<EventLoop>;
+
+    /// Resolves each of the specified service instances, running at most `concurrency`
+    /// resolutions at once on the calling thread, and blocks until all of them have completed.
+    ///
+    /// Returns the result of each resolution in the order of `keys`. A resolution that has not
+    /// completed within `timeout` of being started fails with `Error::BrowserError`.
+    ///
+    /// All resolutions share a single connection to the mDNS daemon.
+    fn resolve_many(
+        keys: &[ServiceKey],
+        concurrency: usize,
+        timeout: Duration,
+    ) -> Vec<Result<ServiceDiscovery>>
+    where
+        Self: Sized;
+}
+
+/// Tracks the keys of a [`TMdnsResolver::resolve_many()`] call, for backends that run the
+/// resolutions on a connection of their own.
+///
+/// [`TMdnsResolver::resolve_many()`]: trait.TMdnsResolver.html#method.resolve_many
+pub(crate) struct ResolveBatch<'a, R> {
+    keys: &'a [ServiceKey],
+    concurrency: usize,
+    timeout: Duration,
+    queued: usize,
+    running: Vec<RunningResolve<R>>,
+    results: Vec<Option<Result<ServiceDiscovery>>>,
+    sender: Sender<(usize, Result<ServiceDiscovery>)>,
+    receiver: Receiver<(usize, Result<ServiceDiscovery>)>,
+}
+
+struct RunningResolve<R> {
+    index: usize,
+    deadline: Instant,
+    _resolver: R,
+}
+
+impl<'a, R: TMdnsResolver> ResolveBatch<'a, R> {
+    pub fn new(keys: &'a [ServiceKey], concurrency: usize, timeout: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            keys,
+            concurrency: concurrency.max(1),
+            timeout,
+            queued: 0,
+            running: Vec::new(),
+            results: keys.iter().map(|_| None).collect(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Starts resolvers for the queued keys until `concurrency` are running, using `start` to
+    /// start each of them on the shared connection.
+    pub fn start_queued(&mut self, mut start: impl FnMut(&mut R) -> Result<()>) {
+        while self.running.len() < self.concurrency && self.queued < self.keys.len() {
+            let index = self.queued;
+            let key = &self.keys[index];
+
+            self.queued += 1;
+
+            let mut resolver = R::new(key.service_type().clone(), key.name());
+
+            if let Some(domain) = key.domain() {
+                resolver.set_domain(domain);
+            }
+
+            let sender = self.sender.clone();
+
+            resolver.set_resolved_callback(Box::new(move |result, _| {
+                // the batch may already have given up on this key
+                let _ = sender.send((index, result));
+            }));
+
+            match start(&mut resolver) {
+                Ok(()) => self.running.push(RunningResolve {
+                    index,
+                    deadline: Instant::now() + self.timeout,
+                    _resolver: resolver,
+                }),
+                Err(e) => self.results[index] = Some(Err(e)),
+            }
+        }
+    }
+
+    /// Returns how long the connection may be waited on before the earliest running resolution
+    /// times out, or `None` if none are running.
+    pub fn next_timeout(&self) -> Option<Duration> {
+        self.running
+            .iter()
+            .map(|resolve| resolve.deadline)
+            .min()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Records the results delivered since the last call and fails the resolutions whose
+    /// timeout has elapsed, dropping the resolvers of both.
+    pub fn collect(&mut self) {
+        while let Ok((index, result)) = self.receiver.try_recv() {
+            if let Some(i) = self.running.iter().position(|r| r.index == index) {
+                self.running.remove(i);
+                self.results[index] = Some(result);
+            }
+        }
+
+        let now = Instant::now();
+        let (keys, timeout, results) = (self.keys, self.timeout, &mut self.results);
+
+        self.running.retain(|resolve| {
+            if now < resolve.deadline {
+                return true;
+            }
+
+            results[resolve.index] = Some(Err(Error::BrowserError(format!(
+                "`{}` was not resolved within {:?}",
+                keys[resolve.index].name(),
+                timeout
+            ))));
+
+            false
+        });
+    }
+
+    /// Fails every key that has not been resolved yet with the specified error of the shared
+    /// connection, dropping the running resolvers.
+    pub fn fail(&mut self, error: &Error) {
+        self.running.clear();
+        self.queued = self.keys.len();
+
+        for (key, result) in self.keys.iter().zip(self.results.iter_mut()) {
+            if result.is_none() {
+                *result = Some(Err(Error::BrowserError(format!(
+                    "could not resolve `{}`: {}",
+                    key.name(),
+                    error
+                ))));
+            }
+        }
+    }
+
+    /// Returns the result of each key, in the order of the keys.
+    pub fn into_results(self) -> Vec<Result<ServiceDiscovery>> {
+        self.results
+            .into_iter()
+            .map(|result| result.expect("every key should have been resolved"))
+            .collect()
+    }
+}
+
+/// Callback invoked from [`MdnsResolver`] once the service has been resolved.
+///
+/// # Arguments
+/// * `service` - The resolved service
+/// * `context` - The optional user context passed through
+///
+/// [`MdnsResolver`]: type.MdnsResolver.html
+pub type ServiceResolvedCallback = dyn Fn(Result<ServiceDiscovery>, Option<Arc<dyn Any>>);
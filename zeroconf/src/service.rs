@@ -1,8 +1,11 @@
 //! Trait definition for cross-platform service.
 
-use crate::{EventLoop, NetworkInterface, Result, ServiceType, TxtRecord};
+use crate::prelude::*;
+use crate::{Error, EventLoop, NetworkInterface, Result, ServiceType, TxtRecord};
 use std::any::Any;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Interface for interacting with underlying mDNS service implementation registration
 /// capabilities.
@@ -17,6 +20,18 @@ pub trait TMdnsService {
     /// may be auto-assigned, in which case in may not be available until after registration.
     fn name(&self) -> Option<&str>;
 
+    /// Sets whether the service should be renamed automatically if its name conflicts with
+    /// another service on the network. Defaults to `true`.
+    ///
+    /// When disabled, a conflict is reported to the [`ServiceRegisteredCallback`] as
+    /// `Error::NameConflict` instead.
+    ///
+    /// [`ServiceRegisteredCallback`]: ../type.ServiceRegisteredCallback.html
+    fn set_auto_rename(&mut self, auto_rename: bool);
+
+    /// Returns true if the service will be renamed automatically on a name conflict.
+    fn auto_rename(&self) -> bool;
+
     /// Sets the network interface to bind this service to.
     ///
     /// Most applications will want to use the default value `NetworkInterface::Unspec` to bind to
@@ -28,8 +43,9 @@ pub trait TMdnsService {
 
     /// Sets the domain on which to advertise the service.
     ///
-    /// Most applications will want to use the default value of `ptr::null()` to register to the
-    /// default domain.
+    /// Most applications will want to leave this unset to register to the default domain
+    /// (usually `local`). Specifying a unicast DNS-SD domain publishes the service as a wide-area
+    /// service, provided the system's mDNS daemon is configured to update that zone.
     fn set_domain(&mut self, domain: &str);
 
     /// Returns the domain on which to advertise the service.
@@ -56,6 +72,23 @@ pub trait TMdnsService {
     /// [`ServiceRegisteredCallback`]: ../type.ServiceRegisteredCallback.html
     fn set_registered_callback(&mut self, registered_callback: Box<ServiceRegisteredCallback>);
 
+    /// Returns a `Receiver` that registration results are sent to instead of a
+    /// [`ServiceRegisteredCallback`], replacing any callback previously set.
+    ///
+    /// Results are still only produced while the `EventLoop` is being polled.
+    ///
+    /// [`ServiceRegisteredCallback`]: ../type.ServiceRegisteredCallback.html
+    fn registered_channel(&mut self) -> Receiver<Result<ServiceRegistration>> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.set_registered_callback(Box::new(move |result, _| {
+            // the receiver may have been dropped, in which case the result is not of interest
+            let _ = sender.send(result);
+        }));
+
+        receiver
+    }
+
     /// Sets the optional user context to pass through to the callback. This is useful if you need
     /// to share state between pre and post-callback. The context type must implement `Any`.
     fn set_context(&mut self, context: Box<dyn Any>);
@@ -66,6 +99,55 @@ pub trait TMdnsService {
     /// Registers and start's the service. Returns an `EventLoop` which can be called to keep
     /// the service alive.
     fn register(&mut self) -> Result<EventLoop>;
+
+    /// Replaces this registered service with `replacement`, e.g. to move it to another port or
+    /// `ServiceType`, without a gap in which neither is discoverable.
+    ///
+    /// `replacement` is registered first and this service is only withdrawn once the replacement
+    /// has been established, at which point `self` becomes the replacement. The returned
+    /// `EventLoop` must be polled in place of the one this service was registered with, which
+    /// should be dropped, since some backends keep the previous registration alive until it is.
+    ///
+    /// Since both services are briefly registered at the same time, a replacement with the same
+    /// name on the same host conflicts with this service and is renamed or rejected according to
+    /// [`auto_rename()`]. Give the replacement a different name to avoid this.
+    ///
+    /// The registered callback of `replacement` is replaced, since its registration is returned
+    /// instead. If the replacement fails to register within `timeout`, this service is left
+    /// registered and the error is returned.
+    ///
+    /// [`auto_rename()`]: #tymethod.auto_rename
+    fn replace_with(
+        &mut self,
+        mut replacement: Self,
+        timeout: Duration,
+    ) -> Result<(EventLoop, ServiceRegistration)>
+    where
+        Self: Sized,
+    {
+        let registrations = replacement.registered_channel();
+        let event_loop = replacement.register()?;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match registrations.try_recv() {
+                Ok(result) => {
+                    let registration = result?;
+                    // dropping the previous service withdraws it from the network
+                    *self = replacement;
+                    return Ok((event_loop, registration));
+                }
+                Err(TryRecvError::Empty) if Instant::now() < deadline => {
+                    event_loop.poll_until(deadline)?;
+                }
+                Err(_) => {
+                    return Err(Error::ServiceError(
+                        "replacement service was not registered in time".into(),
+                    ))
+                }
+            }
+        }
+    }
 }
 
 /// Callback invoked from [`MdnsService`] once it has successfully registered.
@@ -80,9 +162,18 @@ pub type ServiceRegisteredCallback = dyn Fn(Result<ServiceRegistration>, Option<
 /// Represents a registration event for a [`MdnsService`].
 ///
 /// [`MdnsService`]: type.MdnsService.html
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Builder, BuilderDelegate, Debug, Getters, Clone, Default, PartialEq, Eq)]
 pub struct ServiceRegistration {
     name: String,
     service_type: ServiceType,
     domain: String,
+    /// The full DNS name of the registered service instance, e.g. `foo._http._tcp.local`
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    full_name: String,
+    /// The network interface the service was registered on
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    interface: NetworkInterface,
 }
@@ -134,3 +134,18 @@ mod tests {
         assert_eq!(lstrip_underscore("foo"), "foo");
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn serialize_success() {
+        let service_type = ServiceType::with_sub_types("http", "tcp", vec!["printer"]).unwrap();
+
+        let json = serde_json::to_string(&service_type).unwrap();
+        let service_type_de: ServiceType = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(service_type, service_type_de);
+    }
+}
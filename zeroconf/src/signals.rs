@@ -0,0 +1,59 @@
+//! Graceful shutdown of a `ServiceDaemon` on process signals
+
+use crate::{Error, Result, ServiceDaemon};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::process;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Installs handlers for `SIGTERM` and `SIGINT` that shut down the specified daemon, so its
+/// services are unregistered with goodbye packets instead of lingering on the network until
+/// their records expire.
+///
+/// The daemon remains usable until a signal is received. Once one is, the daemon is shut down
+/// through its [`ShutdownToken`], waiting up to `deadline` for it to stop, and the signal is then
+/// sent to the returned `Receiver`, so the application can run its own cleanup and exit.
+///
+/// If `exit` is true, the process instead exits right after the daemon has shut down, with the
+/// conventional exit code of `128 + signal`. This skips the destructors of the rest of the
+/// application.
+///
+/// [`ShutdownToken`]: ../struct.ShutdownToken.html
+pub fn shutdown_on_signals(
+    daemon: &ServiceDaemon,
+    deadline: Duration,
+    exit: bool,
+) -> Result<Receiver<i32>> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])
+        .map_err(|e| Error::ServiceError(format!("could not install signal handlers: {}", e)))?;
+
+    let token = daemon.shutdown_token();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::Builder::new()
+        .name("zeroconf-signals".into())
+        .spawn(move || {
+            let signal = match signals.forever().next() {
+                Some(signal) => signal,
+                None => return,
+            };
+
+            info!("received signal {}, shutting down service daemon", signal);
+
+            if !token.shutdown(deadline) {
+                warn!("service daemon did not shut down within {:?}", deadline);
+            }
+
+            if exit {
+                process::exit(128 + signal);
+            }
+
+            // the receiver may have been dropped, in which case the signal is not of interest
+            let _ = sender.send(signal);
+        })
+        .map_err(|e| Error::ServiceError(format!("could not spawn signal thread: {}", e)))?;
+
+    Ok(receiver)
+}
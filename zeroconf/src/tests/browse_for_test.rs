@@ -0,0 +1,72 @@
+use crate::prelude::*;
+use crate::{MdnsBrowser, ServiceDaemon, ServiceType};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[test]
+fn browse_for_returns_discoveries() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "browse_for_returns_discoveries";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let daemon = ServiceDaemon::new().unwrap();
+    let registrations = daemon
+        .register(
+            service_type.clone(),
+            8080,
+            Some(SERVICE_NAME.into()),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    registrations
+        .receiver()
+        .recv_timeout(Duration::from_secs(10))
+        .unwrap()
+        .unwrap();
+
+    let discoveries = MdnsBrowser::new(service_type)
+        .browse_for(Duration::from_secs(5))
+        .unwrap();
+
+    assert!(discoveries.iter().any(|d| d.name() == SERVICE_NAME));
+
+    daemon.shutdown().unwrap();
+}
+
+#[test]
+fn browse_for_in_explicit_domain() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "browse_for_in_explicit_domain";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let daemon = ServiceDaemon::new().unwrap();
+    let registrations = daemon
+        .register(
+            service_type.clone(),
+            8080,
+            Some(SERVICE_NAME.into()),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    registrations
+        .receiver()
+        .recv_timeout(Duration::from_secs(10))
+        .unwrap()
+        .unwrap();
+
+    let mut browser = MdnsBrowser::new(service_type);
+    browser.set_domain("local");
+    assert_eq!(browser.domain(), Some("local"));
+
+    let discoveries = browser.browse_for(Duration::from_secs(5)).unwrap();
+
+    assert!(discoveries
+        .iter()
+        .any(|d| d.name() == SERVICE_NAME && d.domain() == "local"));
+
+    daemon.shutdown().unwrap();
+}
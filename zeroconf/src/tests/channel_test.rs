@@ -0,0 +1,123 @@
+use crate::prelude::*;
+use crate::{BrowserEvent, MdnsBrowser, MdnsService, ServiceType};
+use std::time::{Duration, Instant};
+
+const TOTAL_TEST_TIME_S: u64 = 30;
+
+#[test]
+fn channels_receive_events() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "channels_receive_events";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let mut service = MdnsService::new(service_type.clone(), 8080);
+    service.set_name(SERVICE_NAME);
+    let registrations = service.registered_channel();
+    let service_loop = service.register().unwrap();
+
+    let mut browser = MdnsBrowser::new(service_type);
+    let events = browser.service_channel();
+    let browser_loop = browser.browse_services().unwrap();
+
+    let start = Instant::now();
+    let mut registered = false;
+    let mut discovered = false;
+
+    while !registered || !discovered {
+        service_loop.poll(Duration::from_millis(100)).unwrap();
+        browser_loop.poll(Duration::from_millis(100)).unwrap();
+
+        if let Ok(result) = registrations.try_recv() {
+            assert_eq!(result.unwrap().name(), SERVICE_NAME);
+            registered = true;
+        }
+
+        while let Ok(event) = events.try_recv() {
+            if let BrowserEvent::Add(service) = event.unwrap() {
+                discovered |= service.name() == SERVICE_NAME;
+            }
+        }
+
+        if start.elapsed() >= Duration::from_secs(TOTAL_TEST_TIME_S) {
+            panic!("test timed out");
+        }
+    }
+}
+
+#[test]
+fn browser_reports_all_for_now() {
+    super::setup();
+
+    // Bonjour only reports `AllForNow` after a browse result, so make sure there is one
+    static SERVICE_NAME: &str = "browser_reports_all_for_now";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let mut service = MdnsService::new(service_type.clone(), 8080);
+    service.set_name(SERVICE_NAME);
+    let service_loop = service.register().unwrap();
+
+    let mut browser = MdnsBrowser::new(service_type);
+    let events = browser.service_channel();
+    let browser_loop = browser.browse_services().unwrap();
+
+    let start = Instant::now();
+
+    loop {
+        service_loop.poll(Duration::from_millis(100)).unwrap();
+        browser_loop.poll(Duration::from_millis(100)).unwrap();
+
+        if events
+            .try_iter()
+            .any(|event| event == Ok(BrowserEvent::AllForNow))
+        {
+            break;
+        }
+
+        if start.elapsed() >= Duration::from_secs(TOTAL_TEST_TIME_S) {
+            panic!("test timed out");
+        }
+    }
+}
+
+#[test]
+fn browser_reports_unresolved_services() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "browser_reports_unresolved_services";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let mut service = MdnsService::new(service_type.clone(), 8080);
+    service.set_name(SERVICE_NAME);
+    let service_loop = service.register().unwrap();
+
+    let mut browser = MdnsBrowser::new(service_type);
+    browser.set_auto_resolve(false);
+    assert!(!browser.auto_resolve());
+
+    let events = browser.service_channel();
+    let browser_loop = browser.browse_services().unwrap();
+
+    let start = Instant::now();
+
+    loop {
+        service_loop.poll(Duration::from_millis(100)).unwrap();
+        browser_loop.poll(Duration::from_millis(100)).unwrap();
+
+        let found = events.try_iter().any(|event| match event.unwrap() {
+            BrowserEvent::Add(_) => panic!("service should not have been resolved"),
+            BrowserEvent::Found(instance) => {
+                instance.name() == SERVICE_NAME && instance.kind() == "_http._tcp"
+            }
+            _ => false,
+        });
+
+        if found {
+            break;
+        }
+
+        if start.elapsed() >= Duration::from_secs(TOTAL_TEST_TIME_S) {
+            panic!("test timed out");
+        }
+    }
+}
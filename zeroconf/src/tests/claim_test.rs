@@ -0,0 +1,27 @@
+use crate::{ServiceClaim, ServiceType};
+use std::time::Duration;
+
+const CLAIM_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn service_claim_is_exclusive() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "service_claim_is_exclusive";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let first = ServiceClaim::try_acquire(service_type.clone(), 8080, SERVICE_NAME, CLAIM_TIMEOUT)
+        .unwrap()
+        .expect("first claim should succeed");
+
+    assert_eq!(first.registration().name(), SERVICE_NAME);
+    assert_eq!(
+        first.registration().full_name(),
+        &format!("{}._http._tcp.local", SERVICE_NAME)
+    );
+
+    let second =
+        ServiceClaim::try_acquire(service_type, 8081, SERVICE_NAME, CLAIM_TIMEOUT).unwrap();
+
+    assert!(second.is_none());
+}
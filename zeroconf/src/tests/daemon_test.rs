@@ -0,0 +1,93 @@
+use crate::{BrowserEvent, ServiceDaemon, ServiceType};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const TOTAL_TEST_TIME_S: u64 = 30;
+
+#[test]
+fn service_daemon_registers_and_browses() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "service_daemon_registers_and_browses";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+    let timeout = Duration::from_secs(TOTAL_TEST_TIME_S);
+
+    let daemon = ServiceDaemon::new().unwrap();
+
+    let registrations = daemon
+        .register(
+            service_type.clone(),
+            8080,
+            Some(SERVICE_NAME.into()),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    let events = daemon.browse(service_type).unwrap();
+
+    let registration = registrations
+        .receiver()
+        .recv_timeout(timeout)
+        .unwrap()
+        .unwrap();
+    assert_eq!(registration.name(), SERVICE_NAME);
+
+    let start = Instant::now();
+
+    loop {
+        let event = events.receiver().recv_timeout(timeout).unwrap().unwrap();
+
+        if let BrowserEvent::Add(service) = event {
+            if service.name() == SERVICE_NAME {
+                break;
+            }
+        }
+
+        if start.elapsed() >= timeout {
+            panic!("test timed out");
+        }
+    }
+
+    daemon.shutdown().unwrap();
+}
+
+#[test]
+fn service_daemon_stop_ends_operation() {
+    super::setup();
+
+    let daemon = ServiceDaemon::new().unwrap();
+    let events = daemon
+        .browse(ServiceType::new("http", "tcp").unwrap())
+        .unwrap();
+
+    events.stop().unwrap();
+
+    // the operation and the senders it owns are dropped once the daemon processes the stop
+    while events
+        .receiver()
+        .recv_timeout(Duration::from_secs(TOTAL_TEST_TIME_S))
+        .is_ok()
+    {}
+
+    assert!(matches!(
+        events.receiver().try_recv(),
+        Err(std::sync::mpsc::TryRecvError::Disconnected)
+    ));
+
+    daemon.shutdown().unwrap();
+}
+
+#[test]
+fn service_daemon_shutdown_token_stops_daemon() {
+    super::setup();
+
+    let daemon = ServiceDaemon::new().unwrap();
+    let token = daemon.shutdown_token();
+
+    assert!(token
+        .clone()
+        .shutdown(Duration::from_secs(TOTAL_TEST_TIME_S)));
+    assert!(daemon
+        .browse(ServiceType::new("http", "tcp").unwrap())
+        .is_err());
+}
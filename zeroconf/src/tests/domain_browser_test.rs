@@ -0,0 +1,29 @@
+use crate::prelude::*;
+use crate::{DomainKind, MdnsDomainBrowser};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn domain_browser_browses_without_error() {
+    super::setup();
+
+    let mut browser = MdnsDomainBrowser::new(DomainKind::Registration);
+    let (sender, receiver) = mpsc::channel();
+
+    browser.set_domain_callback(Box::new(move |result, _| {
+        sender.send(result).unwrap();
+    }));
+
+    assert_eq!(browser.kind(), DomainKind::Registration);
+
+    let event_loop = browser.browse_domains().unwrap();
+
+    event_loop
+        .poll_until(Instant::now() + Duration::from_secs(2))
+        .unwrap();
+
+    // which domains are listed depends on the network, but none of the events may be failures
+    for result in receiver.try_iter() {
+        result.unwrap();
+    }
+}
@@ -83,3 +83,50 @@ fn event_loop_long_polls() {
 
     assert!(LONG_POLL_MAX_ITERS > iterations);
 }
+
+#[test]
+fn event_loop_reports_liveness() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "event_loop_liveness_test_service";
+    let mut service = MdnsService::new(ServiceType::new("http", "tcp").unwrap(), 8080);
+
+    service.set_name(SERVICE_NAME);
+
+    let event_loop = service.register().unwrap();
+    event_loop.poll(FAST_SPIN_TIMEOUT).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+
+    assert!(event_loop.is_running());
+    assert_eq!(event_loop.last_error(), None);
+    assert!(event_loop.uptime() >= Duration::from_millis(10));
+}
+
+#[test]
+fn event_loop_polls_until_dispatch() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "event_loop_poll_until_test_service";
+    let mut service = MdnsService::new(ServiceType::new("http", "tcp").unwrap(), 8080);
+
+    service.set_name(SERVICE_NAME);
+    service.set_registered_callback(Box::new(|_, _| {
+        debug!("Service published");
+    }));
+
+    let event_loop = service.register().unwrap();
+    let dispatched = event_loop
+        .poll_until(Instant::now() + Duration::from_secs(10))
+        .unwrap();
+
+    assert_eq!(dispatched, 1);
+    assert_eq!(event_loop.dispatch_count(), 1);
+
+    let start = Instant::now();
+    let dispatched = event_loop
+        .poll_until(start + Duration::from_millis(100))
+        .unwrap();
+
+    assert_eq!(dispatched, 0);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
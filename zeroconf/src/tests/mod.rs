@@ -6,5 +6,12 @@ pub(crate) fn setup() {
     INIT.call_once(env_logger::init);
 }
 
+mod browse_for_test;
+mod channel_test;
+mod claim_test;
+mod daemon_test;
+mod domain_browser_test;
 mod event_loop_test;
+mod pool_test;
+mod resolver_test;
 mod service_test;
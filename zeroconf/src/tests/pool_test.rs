@@ -0,0 +1,35 @@
+use crate::prelude::*;
+use crate::{DiscoveredPool, MdnsService, ServiceType};
+use std::time::{Duration, Instant};
+
+const TOTAL_TEST_TIME_S: u64 = 30;
+
+#[test]
+fn discovered_pool_connects_to_instances() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "discovered_pool_connects_to_instances";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let mut service = MdnsService::new(service_type.clone(), 8080);
+    service.set_name(SERVICE_NAME);
+    let service_loop = service.register().unwrap();
+
+    let pool = DiscoveredPool::new(service_type, Box::new(|service| Ok(*service.port()))).unwrap();
+    let start = Instant::now();
+
+    let key = loop {
+        if let Some(key) = pool.keys().into_iter().find(|k| k.name() == SERVICE_NAME) {
+            break key;
+        }
+
+        service_loop.poll(Duration::from_millis(100)).unwrap();
+        pool.poll(Duration::from_millis(100)).unwrap();
+
+        if start.elapsed() >= Duration::from_secs(TOTAL_TEST_TIME_S) {
+            panic!("test timed out");
+        }
+    };
+
+    assert_eq!(pool.with_connection(&key, |port| *port), Some(8080));
+}
@@ -0,0 +1,101 @@
+use crate::prelude::*;
+use crate::{MdnsResolver, NetworkInterface, ServiceDaemon, ServiceKey, ServiceType};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[test]
+fn resolver_resolves_known_instance() {
+    super::setup();
+
+    static SERVICE_NAME: &str = "resolver_resolves_known_instance";
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+
+    let daemon = ServiceDaemon::new().unwrap();
+    let mut txt = HashMap::new();
+    txt.insert("foo".to_string(), "bar".to_string());
+
+    let registrations = daemon
+        .register(service_type.clone(), 8080, Some(SERVICE_NAME.into()), txt)
+        .unwrap();
+
+    registrations
+        .receiver()
+        .recv_timeout(Duration::from_secs(10))
+        .unwrap()
+        .unwrap();
+
+    let mut resolver = MdnsResolver::new(service_type.clone(), SERVICE_NAME);
+    let results = resolver.resolved_channel();
+    let event_loop = resolver.resolve().unwrap();
+
+    event_loop
+        .poll_until(Instant::now() + Duration::from_secs(10))
+        .unwrap();
+
+    let service = results.try_recv().unwrap().unwrap();
+
+    assert_eq!(service.name(), SERVICE_NAME);
+    assert_eq!(service.service_type(), &service_type);
+    assert_eq!(service.domain(), "local");
+    assert_eq!(*service.port(), 8080);
+    assert_ne!(*service.interface(), NetworkInterface::Unspec);
+    assert_eq!(
+        service.txt().as_ref().unwrap().get("foo"),
+        Some("bar".to_string())
+    );
+
+    daemon.shutdown().unwrap();
+}
+
+#[test]
+fn resolver_resolve_many_preserves_order() {
+    super::setup();
+
+    static SERVICE_NAMES: [&str; 2] = [
+        "resolver_resolve_many_preserves_order_1",
+        "resolver_resolve_many_preserves_order_2",
+    ];
+
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+    let daemon = ServiceDaemon::new().unwrap();
+
+    for (port, name) in (8080..).zip(SERVICE_NAMES.iter()) {
+        daemon
+            .register(
+                service_type.clone(),
+                port,
+                Some(name.to_string()),
+                HashMap::new(),
+            )
+            .unwrap()
+            .receiver()
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap()
+            .unwrap();
+    }
+
+    let key = |name: &str| {
+        ServiceKey::builder()
+            .name(name.into())
+            .service_type(service_type.clone())
+            .build()
+            .unwrap()
+    };
+
+    let keys = [
+        key(SERVICE_NAMES[1]),
+        key("resolver_resolve_many_preserves_order_missing"),
+        key(SERVICE_NAMES[0]),
+    ];
+
+    let results = MdnsResolver::resolve_many(&keys, 2, Duration::from_secs(5));
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().name(), SERVICE_NAMES[1]);
+    assert_eq!(*results[0].as_ref().unwrap().port(), 8081);
+    results[1].as_ref().expect_err("service should not resolve");
+    assert_eq!(results[2].as_ref().unwrap().name(), SERVICE_NAMES[0]);
+    assert_eq!(*results[2].as_ref().unwrap().port(), 8080);
+
+    daemon.shutdown().unwrap();
+}
@@ -71,6 +71,7 @@ fn service_register_is_browsable() {
                     service.domain()
                 );
             }
+            event => debug!("Browser event: {:?}", event),
         }));
 
         let event_loop = browser.browse_services().unwrap();
@@ -111,3 +112,53 @@ fn service_register_is_browsable() {
 
     assert!(!context.lock().unwrap().timed_out);
 }
+
+#[test]
+fn service_register_in_explicit_domain() {
+    super::setup();
+
+    let mut service = MdnsService::new(ServiceType::new("http", "tcp").unwrap(), 8080);
+
+    service.set_name("service_register_in_explicit_domain");
+    service.set_domain("local.");
+
+    let registrations = service.registered_channel();
+    let event_loop = service.register().unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+
+    event_loop.poll_until(deadline).unwrap();
+
+    let registration = registrations.try_recv().unwrap().unwrap();
+
+    assert_eq!(registration.domain(), "local");
+    assert_eq!(service.domain(), Some("local."));
+}
+
+#[test]
+fn service_replace_with_new_port() {
+    super::setup();
+
+    let service_type = ServiceType::new("http", "tcp").unwrap();
+    let timeout = Duration::from_secs(10);
+
+    let mut service = MdnsService::new(service_type.clone(), 8080);
+    service.set_name("service_replace_with_old");
+
+    let registrations = service.registered_channel();
+    let event_loop = service.register().unwrap();
+
+    event_loop
+        .poll_until(std::time::Instant::now() + timeout)
+        .unwrap();
+
+    registrations.try_recv().unwrap().unwrap();
+
+    let mut replacement = MdnsService::new(service_type, 8081);
+    replacement.set_name("service_replace_with_new");
+
+    let (_event_loop, registration) = service.replace_with(replacement, timeout).unwrap();
+    drop(event_loop);
+
+    assert_eq!(registration.name(), "service_replace_with_new");
+    assert_eq!(service.name(), Some("service_replace_with_new"));
+}
@@ -0,0 +1,167 @@
+//! Helpers for establishing TLS connections to discovered services
+
+use crate::prelude::*;
+use crate::{Error, Result, ServiceDiscovery};
+use rustls_pki_types::ServerName;
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+/// Key under which a service may publish the hex encoded SHA-256 fingerprint of the DER encoded
+/// certificate it presents
+pub const TXT_CERT_FINGERPRINT_KEY: &str = "certfp";
+
+impl ServiceDiscovery {
+    /// Returns the `ServerName` to connect to this service with.
+    ///
+    /// The SRV target host name is preferred, since certificates are rarely issued for link-local
+    /// addresses. The resolved address is used if the host name is not a valid DNS name.
+    pub fn server_name(&self) -> Result<ServerName<'static>> {
+        let host_name = self.host_name().trim_end_matches('.');
+
+        if let Ok(name) = ServerName::try_from(host_name.to_string()) {
+            return Ok(name);
+        }
+
+        self.ip_addr()
+            .map(|address| ServerName::IpAddress(address.into()))
+            .map_err(|_| {
+                Error::BrowserError(format!(
+                    "neither host name `{}` nor address `{}` is a valid server name",
+                    self.host_name(),
+                    self.address()
+                ))
+            })
+    }
+
+    /// Verifies that the specified DER encoded certificate matches the fingerprint this service
+    /// published under [`TXT_CERT_FINGERPRINT_KEY`].
+    ///
+    /// Returns `Error::InvalidTxtRecord` if no fingerprint was published and
+    /// `Error::CertificateMismatch` if it does not match.
+    ///
+    /// The fingerprint comes from an unauthenticated TXT record, so any host on the link can
+    /// advertise a service with the fingerprint of its own certificate. This only proves that the
+    /// certificate matches the advertisement, not who made it. Verify the record with
+    /// `ServiceDiscovery::verify_txt()` from the `txt-signing` feature first, or pin the
+    /// fingerprint out of band.
+    ///
+    /// [`TXT_CERT_FINGERPRINT_KEY`]: constant.TXT_CERT_FINGERPRINT_KEY.html
+    pub fn verify_certificate(&self, certificate: &[u8]) -> Result<()> {
+        let expected = self
+            .txt()
+            .as_ref()
+            .and_then(|txt| txt.get(TXT_CERT_FINGERPRINT_KEY))
+            .ok_or_else(|| {
+                Error::InvalidTxtRecord(format!("missing `{}`", TXT_CERT_FINGERPRINT_KEY))
+            })?;
+
+        let actual = certificate_fingerprint(certificate);
+
+        if actual.eq_ignore_ascii_case(expected.trim()) {
+            Ok(())
+        } else {
+            Err(Error::CertificateMismatch(format!(
+                "expected fingerprint `{}` but found `{}`",
+                expected, actual
+            )))
+        }
+    }
+}
+
+/// Returns the hex encoded SHA-256 fingerprint of the specified DER encoded certificate, as
+/// published under [`TXT_CERT_FINGERPRINT_KEY`].
+///
+/// [`TXT_CERT_FINGERPRINT_KEY`]: constant.TXT_CERT_FINGERPRINT_KEY.html
+pub fn certificate_fingerprint(certificate: &[u8]) -> String {
+    Sha256::digest(certificate)
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ServiceType, TxtRecord};
+
+    fn discovery(host_name: &str, address: &str, txt: Option<TxtRecord>) -> ServiceDiscovery {
+        ServiceDiscovery::builder()
+            .name("foo".into())
+            .service_type(ServiceType::new("https", "tcp").unwrap())
+            .domain("local".into())
+            .host_name(host_name.into())
+            .address(address.into())
+            .port(443)
+            .txt(txt)
+            .build()
+            .unwrap()
+    }
+
+    fn fingerprinted(certificate: &[u8]) -> ServiceDiscovery {
+        let mut txt = TxtRecord::new();
+        txt.insert(
+            TXT_CERT_FINGERPRINT_KEY,
+            &certificate_fingerprint(certificate),
+        )
+        .unwrap();
+        discovery("foo.local", "192.168.1.2", Some(txt))
+    }
+
+    #[test]
+    fn server_name_prefers_host_name() {
+        crate::tests::setup();
+
+        let name = discovery("foo.local.", "192.168.1.2", None)
+            .server_name()
+            .unwrap();
+
+        assert_eq!(name, ServerName::try_from("foo.local").unwrap());
+    }
+
+    #[test]
+    fn server_name_falls_back_to_address() {
+        crate::tests::setup();
+
+        let name = discovery("not a host", "192.168.1.2", None)
+            .server_name()
+            .unwrap();
+
+        assert_eq!(name, ServerName::try_from("192.168.1.2").unwrap());
+    }
+
+    #[test]
+    fn certificate_fingerprint_success() {
+        crate::tests::setup();
+
+        assert_eq!(
+            certificate_fingerprint(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn verify_certificate_success() {
+        crate::tests::setup();
+
+        assert_eq!(fingerprinted(b"cert").verify_certificate(b"cert"), Ok(()));
+    }
+
+    #[test]
+    fn verify_certificate_rejects_mismatch() {
+        crate::tests::setup();
+
+        let result = fingerprinted(b"cert").verify_certificate(b"spoofed");
+        assert!(matches!(result, Err(Error::CertificateMismatch(_))));
+    }
+
+    #[test]
+    fn verify_certificate_requires_fingerprint() {
+        crate::tests::setup();
+
+        let result = discovery("foo.local", "192.168.1.2", None).verify_certificate(b"cert");
+        assert!(matches!(result, Err(Error::InvalidTxtRecord(_))));
+    }
+}
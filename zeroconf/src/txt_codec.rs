@@ -0,0 +1,256 @@
+//! Transparent compression of TXT record values too large for a single entry
+
+use crate::prelude::*;
+use crate::{Error, Result, TxtRecord};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Maximum length in bytes of a single `key=value` TXT record entry
+const MAX_ENTRY_LEN: usize = 255;
+/// Suffix of the reserved keys holding a compressed value
+const COMPRESSED_KEY_SUFFIX: &str = "~z";
+/// Maximum length in bytes of the data of a TXT record
+const MAX_TXT_RECORD_LEN: usize = 65535;
+/// Maximum number of chunks a compressed value may be split into; each full chunk occupies a
+/// whole entry and its length byte, so no more can fit in a single record
+const MAX_CHUNKS: usize = MAX_TXT_RECORD_LEN / (MAX_ENTRY_LEN + 1);
+/// Maximum length in bytes of a decompressed value, bounding the memory a peer can make us
+/// allocate with a highly compressed value
+const MAX_DECOMPRESSED_LEN: u64 = 1 << 20;
+
+/// Extension of [`TTxtRecord`] that transparently compresses values too large to fit in a single
+/// TXT record entry.
+///
+/// Large values are gzip compressed, base64 encoded and split across reserved keys of the form
+/// `<key>~z<index>`, with `<key>~z` holding the number of chunks. Only peers that also use this
+/// extension can read these values back in their original form.
+///
+/// [`TTxtRecord`]: ../txt_record/trait.TTxtRecord.html
+pub trait TCompressedTxtRecord: TTxtRecord {
+    /// Inserts the specified value at the specified key, compressing it if it does not fit in a
+    /// single entry.
+    fn insert_compressed(&mut self, key: &str, value: &str) -> Result<()>;
+
+    /// Returns the value at the specified key, decompressing it if it was inserted compressed,
+    /// or `None` if no such key exists.
+    fn get_decompressed(&self, key: &str) -> Result<Option<String>>;
+}
+
+impl TCompressedTxtRecord for TxtRecord {
+    fn insert_compressed(&mut self, key: &str, value: &str) -> Result<()> {
+        remove_chunks(self, key);
+
+        if key.len() + value.len() < MAX_ENTRY_LEN {
+            return self.insert(key, value);
+        }
+
+        self.remove(key);
+
+        let chunks = encode_chunks(key, value)?;
+
+        self.insert(&count_key(key), &chunks.len().to_string())?;
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            self.insert(&chunk_key(key, index), chunk)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_decompressed(&self, key: &str) -> Result<Option<String>> {
+        let count = match self.get(&count_key(key)) {
+            Some(count) => count
+                .parse::<usize>()
+                .ok()
+                .filter(|count| *count <= MAX_CHUNKS)
+                .ok_or_else(|| invalid_value(key, "invalid chunk count"))?,
+            None => return Ok(self.get(key)),
+        };
+
+        let encoded = (0..count)
+            .map(|index| {
+                self.get(&chunk_key(key, index))
+                    .ok_or_else(|| invalid_value(key, "missing chunk"))
+            })
+            .collect::<Result<String>>()?;
+
+        decode(key, &encoded).map(Some)
+    }
+}
+
+fn remove_chunks(txt: &mut TxtRecord, key: &str) {
+    let count = txt
+        .remove(&count_key(key))
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    for index in 0..count {
+        txt.remove(&chunk_key(key, index));
+    }
+}
+
+fn count_key(key: &str) -> String {
+    format!("{}{}", key, COMPRESSED_KEY_SUFFIX)
+}
+
+fn chunk_key(key: &str, index: usize) -> String {
+    format!("{}{}{}", key, COMPRESSED_KEY_SUFFIX, index)
+}
+
+fn encode_chunks(key: &str, value: &str) -> Result<Vec<String>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+
+    encoder
+        .write_all(value.as_bytes())
+        .map_err(|e| invalid_value(key, &e.to_string()))?;
+
+    let compressed = encoder
+        .finish()
+        .map_err(|e| invalid_value(key, &e.to_string()))?;
+
+    // leave room for the widest chunk key and the '=' separator
+    let chunk_len = MAX_ENTRY_LEN
+        .checked_sub(chunk_key(key, MAX_CHUNKS - 1).len() + 1)
+        .filter(|len| *len > 0)
+        .ok_or_else(|| invalid_value(key, "key too long"))?;
+
+    let chunks = STANDARD
+        .encode(compressed)
+        .as_bytes()
+        .chunks(chunk_len)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>();
+
+    if chunks.len() > MAX_CHUNKS {
+        return Err(invalid_value(key, "value too large"));
+    }
+
+    Ok(chunks)
+}
+
+fn decode(key: &str, encoded: &str) -> Result<String> {
+    let compressed = STANDARD
+        .decode(encoded)
+        .map_err(|e| invalid_value(key, &e.to_string()))?;
+
+    let mut value = String::new();
+
+    GzDecoder::new(compressed.as_slice())
+        .take(MAX_DECOMPRESSED_LEN + 1)
+        .read_to_string(&mut value)
+        .map_err(|e| invalid_value(key, &e.to_string()))?;
+
+    if value.len() as u64 > MAX_DECOMPRESSED_LEN {
+        return Err(invalid_value(key, "decompressed value too large"));
+    }
+
+    Ok(value)
+}
+
+fn invalid_value(key: &str, reason: &str) -> Error {
+    Error::InvalidTxtRecord(format!("compressed value at `{}`: {}", key, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_value() -> String {
+        (0..200).map(|i| format!("item-{};", i)).collect()
+    }
+
+    #[test]
+    fn encode_chunks_fit_in_entry() {
+        let chunks = encode_chunks("state", &large_value()).unwrap();
+        let longest = chunks.iter().map(String::len).max().unwrap();
+        assert!(chunk_key("state", chunks.len() - 1).len() + longest < MAX_ENTRY_LEN);
+    }
+
+    #[test]
+    fn encode_chunks_rejects_long_key() {
+        encode_chunks(&"k".repeat(MAX_ENTRY_LEN), "v").expect_err("key too long");
+    }
+
+    #[test]
+    fn decode_round_trips_encode_chunks() {
+        let value = large_value();
+        let encoded = encode_chunks("state", &value).unwrap().concat();
+        assert_eq!(decode("state", &encoded).unwrap(), value);
+    }
+
+    #[test]
+    fn decode_rejects_oversized_value() {
+        let value = "a".repeat(MAX_DECOMPRESSED_LEN as usize + 1);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(value.as_bytes()).unwrap();
+        let encoded = STANDARD.encode(encoder.finish().unwrap());
+
+        decode("state", &encoded).expect_err("decompressed value too large");
+    }
+
+    #[test]
+    fn decode_rejects_garbage() {
+        decode("state", "not compressed").expect_err("invalid compressed value");
+    }
+
+    #[test]
+    fn insert_compressed_small_value_is_plain() {
+        crate::tests::setup();
+
+        let mut record = TxtRecord::new();
+        record.insert_compressed("foo", "bar").unwrap();
+
+        assert_eq!(record.get("foo").unwrap(), "bar");
+        assert_eq!(record.get_decompressed("foo").unwrap().unwrap(), "bar");
+    }
+
+    #[test]
+    fn insert_compressed_large_value_round_trips() {
+        crate::tests::setup();
+
+        let value = large_value();
+        let mut record = TxtRecord::new();
+        record.insert_compressed("state", &value).unwrap();
+
+        assert!(!record.contains_key("state"));
+        assert_eq!(record.get_decompressed("state").unwrap().unwrap(), value);
+    }
+
+    #[test]
+    fn insert_compressed_replaces_previous_chunks() {
+        crate::tests::setup();
+
+        let mut record = TxtRecord::new();
+        record.insert_compressed("state", &large_value()).unwrap();
+        record.insert_compressed("state", "small").unwrap();
+
+        assert_eq!(record.len(), 1);
+        assert_eq!(record.get_decompressed("state").unwrap().unwrap(), "small");
+    }
+
+    #[test]
+    fn get_decompressed_rejects_excessive_chunk_count() {
+        crate::tests::setup();
+
+        let mut record = TxtRecord::new();
+        record
+            .insert(&count_key("state"), &(MAX_CHUNKS + 1).to_string())
+            .unwrap();
+
+        record
+            .get_decompressed("state")
+            .expect_err("invalid chunk count");
+    }
+
+    #[test]
+    fn get_decompressed_returns_none_if_missing() {
+        crate::tests::setup();
+
+        let record = TxtRecord::new();
+        assert_eq!(record.get_decompressed("foo").unwrap(), None);
+    }
+}
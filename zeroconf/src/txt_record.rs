@@ -44,6 +44,18 @@ pub trait TTxtRecord: Clone + PartialEq + Eq + Debug {
     /// Returns a new iterator over the records values.
     fn values<'a>(&'a self) -> Box<dyn Iterator<Item = String> + 'a>;
 
+    /// Returns a new record with the entries at the specified keys published first, in the order
+    /// given, followed by the remaining entries in their current order.
+    ///
+    /// Some consumers, such as AirPrint and AirPlay clients, require certain keys to appear first
+    /// in the published record. Keys that are not present in this record are ignored.
+    fn with_order(self, keys: &[&str]) -> Self;
+
+    /// Returns the record encoded as TXT record data, in the order it is published.
+    ///
+    /// An empty record is encoded as a single empty string, `[0]`, as required by RFC 6763.
+    fn to_bytes(&self) -> Vec<u8>;
+
     /// Returns true if there are no entries in the record.
     fn is_empty(&self) -> bool {
         self.len() == 0
@@ -59,6 +71,26 @@ pub trait TTxtRecord: Clone + PartialEq + Eq + Debug {
     }
 }
 
+/// Returns the specified entries, given in publishing order, with the entries at `keys` moved to
+/// the front.
+pub(crate) fn ordered_entries(
+    mut entries: Vec<(String, String)>,
+    keys: &[&str],
+) -> Vec<(String, String)> {
+    let mut ordered = keys
+        .iter()
+        .filter_map(|key| {
+            entries
+                .iter()
+                .position(|(k, _)| k == key)
+                .map(|i| entries.remove(i))
+        })
+        .collect::<Vec<_>>();
+
+    ordered.append(&mut entries);
+    ordered
+}
+
 impl From<HashMap<String, String>> for TxtRecord {
     fn from(map: HashMap<String, String>) -> TxtRecord {
         let mut record = TxtRecord::new();
@@ -180,6 +212,42 @@ mod tests {
         assert!(record.get("foo").is_none());
     }
 
+    #[test]
+    fn remove_preserves_order() {
+        crate::tests::setup();
+        let mut record = TxtRecord::new();
+        record.insert("foo", "1").unwrap();
+        record.insert("bar", "2").unwrap();
+        record.insert("baz", "3").unwrap();
+
+        let expected = record.keys().filter(|k| k != "bar").collect::<Vec<_>>();
+        record.remove("bar");
+
+        assert_eq!(record.keys().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn with_order_publishes_keys_first() {
+        crate::tests::setup();
+        let mut record = TxtRecord::new();
+        record.insert("foo", "1").unwrap();
+        record.insert("txtvers", "1").unwrap();
+        record.insert("bar", "2").unwrap();
+        record.insert("rp", "3").unwrap();
+
+        let record = record.with_order(&["txtvers", "missing", "rp"]);
+
+        assert_eq!(record.len(), 4);
+        assert!(record.to_bytes().starts_with(b"\x09txtvers=1\x04rp=3"));
+        assert_eq!(record.get("rp").unwrap(), "3");
+    }
+
+    #[test]
+    fn to_bytes_encodes_empty_record_as_empty_string() {
+        crate::tests::setup();
+        assert_eq!(TxtRecord::new().to_bytes(), vec![0]);
+    }
+
     #[test]
     fn remove_returns_previous_value() {
         crate::tests::setup();
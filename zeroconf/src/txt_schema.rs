@@ -0,0 +1,222 @@
+//! Versioned, typed TXT record payloads
+
+use crate::prelude::*;
+use crate::{Error, Result, TxtRecord};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Key holding the payload version, as recommended by [RFC 6763 section 6.7].
+///
+/// [RFC 6763 section 6.7]: https://www.rfc-editor.org/rfc/rfc6763#section-6.7
+pub const TXT_VERSION_KEY: &str = "txtvers";
+
+/// A typed payload that is published in a TXT record under a declared version.
+pub trait TxtSchema {
+    /// The version published under [`TXT_VERSION_KEY`] alongside this payload.
+    ///
+    /// [`TXT_VERSION_KEY`]: constant.TXT_VERSION_KEY.html
+    const VERSION: u32;
+
+    /// Inserts the fields of this payload into the specified `TxtRecord`.
+    fn write_txt(&self, txt: &mut TxtRecord) -> Result<()>;
+
+    /// Returns a new `TxtRecord` containing this payload and its version.
+    fn to_txt_record(&self) -> Result<TxtRecord> {
+        let mut txt = TxtRecord::new();
+        txt.insert(TXT_VERSION_KEY, &Self::VERSION.to_string())?;
+        self.write_txt(&mut txt)?;
+        Ok(txt)
+    }
+}
+
+/// Callback that decodes a `TxtRecord` of a specific version into `T`.
+pub type TxtSchemaDecoder<T> = dyn Fn(&TxtRecord) -> Result<T>;
+
+/// Decodes TXT records into a typed payload `T` by dispatching on their published version.
+///
+/// Register one decoder per supported version; decoders for older versions typically upgrade
+/// their payload into the current representation of `T`.
+pub struct TxtSchemaRegistry<T> {
+    decoders: HashMap<u32, Box<TxtSchemaDecoder<T>>>,
+}
+
+impl<T> TxtSchemaRegistry<T> {
+    /// Creates a new registry with no supported versions.
+    pub fn new() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Registers the decoder for the specified `version`, replacing any previous one.
+    pub fn register(&mut self, version: u32, decoder: Box<TxtSchemaDecoder<T>>) -> &mut Self {
+        self.decoders.insert(version, decoder);
+        self
+    }
+
+    /// Returns true if a decoder is registered for the specified `version`.
+    pub fn supports(&self, version: u32) -> bool {
+        self.decoders.contains_key(&version)
+    }
+
+    /// Decodes the specified `TxtRecord` with the decoder registered for its version.
+    ///
+    /// Returns `Error::UnsupportedVersion` if no decoder is registered for the version, or
+    /// `Error::InvalidTxtRecord` if the record does not declare a valid version.
+    pub fn decode(&self, txt: &TxtRecord) -> Result<T> {
+        let version = txt_version(txt)?;
+
+        let decoder = self
+            .decoders
+            .get(&version)
+            .ok_or(Error::UnsupportedVersion(version))?;
+
+        decoder(txt)
+    }
+}
+
+impl<T> Default for TxtSchemaRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for TxtSchemaRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxtSchemaRegistry")
+            .field("versions", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Returns the version declared by the specified `TxtRecord` under [`TXT_VERSION_KEY`].
+///
+/// [`TXT_VERSION_KEY`]: constant.TXT_VERSION_KEY.html
+pub fn txt_version(txt: &TxtRecord) -> Result<u32> {
+    let version = txt
+        .get(TXT_VERSION_KEY)
+        .ok_or_else(|| Error::InvalidTxtRecord(format!("missing `{}`", TXT_VERSION_KEY)))?;
+
+    version
+        .parse()
+        .map_err(|_| Error::InvalidTxtRecord(format!("invalid `{}`: {}", TXT_VERSION_KEY, version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Printer {
+        model: String,
+        color: bool,
+    }
+
+    impl TxtSchema for Printer {
+        const VERSION: u32 = 2;
+
+        fn write_txt(&self, txt: &mut TxtRecord) -> Result<()> {
+            txt.insert("model", &self.model)?;
+            txt.insert("color", if self.color { "T" } else { "F" })
+        }
+    }
+
+    fn registry() -> TxtSchemaRegistry<Printer> {
+        let mut registry = TxtSchemaRegistry::new();
+
+        registry
+            .register(
+                1,
+                Box::new(|txt| {
+                    Ok(Printer {
+                        model: txt.get("ty").unwrap_or_default(),
+                        color: false,
+                    })
+                }),
+            )
+            .register(
+                2,
+                Box::new(|txt| {
+                    Ok(Printer {
+                        model: txt.get("model").unwrap_or_default(),
+                        color: txt.get("color").as_deref() == Some("T"),
+                    })
+                }),
+            );
+
+        registry
+    }
+
+    #[test]
+    fn to_txt_record_includes_version() {
+        crate::tests::setup();
+
+        let printer = Printer {
+            model: "LaserJet".into(),
+            color: true,
+        };
+
+        let txt = printer.to_txt_record().unwrap();
+
+        assert_eq!(txt.get(TXT_VERSION_KEY).unwrap(), "2");
+        assert_eq!(txt.get("model").unwrap(), "LaserJet");
+    }
+
+    #[test]
+    fn decode_round_trips_current_version() {
+        crate::tests::setup();
+
+        let printer = Printer {
+            model: "LaserJet".into(),
+            color: true,
+        };
+
+        let txt = printer.to_txt_record().unwrap();
+
+        assert_eq!(registry().decode(&txt).unwrap(), printer);
+    }
+
+    #[test]
+    fn decode_upgrades_older_version() {
+        crate::tests::setup();
+
+        let mut txt = TxtRecord::new();
+        txt.insert(TXT_VERSION_KEY, "1").unwrap();
+        txt.insert("ty", "DeskJet").unwrap();
+
+        assert_eq!(
+            registry().decode(&txt).unwrap(),
+            Printer {
+                model: "DeskJet".into(),
+                color: false,
+            }
+        );
+    }
+
+    #[test]
+    fn decode_returns_unsupported_version() {
+        crate::tests::setup();
+
+        let mut txt = TxtRecord::new();
+        txt.insert(TXT_VERSION_KEY, "3").unwrap();
+
+        assert_eq!(registry().decode(&txt), Err(Error::UnsupportedVersion(3)));
+    }
+
+    #[test]
+    fn txt_version_requires_version_key() {
+        crate::tests::setup();
+
+        txt_version(&TxtRecord::new()).expect_err("missing `txtvers`");
+    }
+
+    #[test]
+    fn txt_version_requires_number() {
+        crate::tests::setup();
+
+        let mut txt = TxtRecord::new();
+        txt.insert(TXT_VERSION_KEY, "two").unwrap();
+
+        txt_version(&txt).expect_err("invalid `txtvers`");
+    }
+}
@@ -0,0 +1,334 @@
+//! Signing and verification of TXT record payloads
+
+use crate::prelude::*;
+use crate::{Error, Result, ServiceDiscovery, ServiceType, TxtRecord};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+/// Reserved key holding the identifier of the key a record was signed with
+pub const TXT_KEY_ID_KEY: &str = "~kid";
+/// Reserved key holding the base64 encoded ed25519 signature of a record
+pub const TXT_SIGNATURE_KEY: &str = "~sig";
+
+/// The service a signed TXT record is published for. Its fields are covered by the signature, so
+/// that a signed record copied into another advertisement fails to verify.
+#[derive(Debug, Getters, Builder, BuilderDelegate, Clone, PartialEq, Eq)]
+pub struct SignedService {
+    /// The instance name the service is registered under
+    name: String,
+    /// The type of the service; sub-types are not covered
+    service_type: ServiceType,
+    /// The domain the service is registered in, e.g. "local"
+    domain: String,
+    /// The port the service is registered on
+    port: u16,
+}
+
+impl From<&ServiceDiscovery> for SignedService {
+    fn from(service: &ServiceDiscovery) -> Self {
+        Self {
+            name: service.name().clone(),
+            service_type: service.service_type().clone(),
+            domain: service.domain().clone(),
+            port: *service.port(),
+        }
+    }
+}
+
+/// Extension of [`TTxtRecord`] for signing a record's entries with an application-provided
+/// ed25519 key, allowing peers to reject spoofed advertisements.
+///
+/// The signature covers every entry except the signature itself, including the key identifier,
+/// independent of the order the entries are published in, along with the name, type, domain and
+/// port of the [`SignedService`] the record is published for. The host name and addresses the
+/// service resolves to are not covered.
+///
+/// [`TTxtRecord`]: ../txt_record/trait.TTxtRecord.html
+/// [`SignedService`]: struct.SignedService.html
+pub trait TSignedTxtRecord: TTxtRecord {
+    /// Signs the entries of this record for the specified service with the specified key,
+    /// storing `key_id` and the signature under the reserved keys [`TXT_KEY_ID_KEY`] and
+    /// [`TXT_SIGNATURE_KEY`].
+    ///
+    /// Any previous signature is replaced, so this must be called after all other entries have
+    /// been inserted. The service must be registered under exactly the signed name, so automatic
+    /// renaming should be disabled with `TMdnsService::set_auto_rename()`.
+    ///
+    /// [`TXT_KEY_ID_KEY`]: constant.TXT_KEY_ID_KEY.html
+    /// [`TXT_SIGNATURE_KEY`]: constant.TXT_SIGNATURE_KEY.html
+    fn sign(&mut self, key_id: &str, key: &SigningKey, service: &SignedService) -> Result<()>;
+
+    /// Returns the identifier of the key this record claims to be signed with, or `None` if the
+    /// record is unsigned. Use this to look up the key to pass to [`verify()`].
+    ///
+    /// [`verify()`]: #tymethod.verify
+    fn signing_key_id(&self) -> Option<String>;
+
+    /// Verifies the signature of this record for the specified service against the specified
+    /// key.
+    ///
+    /// Returns `Error::InvalidTxtRecord` if the record is unsigned, the signature is malformed or
+    /// it does not match the entries of the record and the service.
+    fn verify(&self, key: &VerifyingKey, service: &SignedService) -> Result<()>;
+}
+
+impl TSignedTxtRecord for TxtRecord {
+    fn sign(&mut self, key_id: &str, key: &SigningKey, service: &SignedService) -> Result<()> {
+        self.remove(TXT_SIGNATURE_KEY);
+        self.insert(TXT_KEY_ID_KEY, key_id)?;
+
+        let signature = key.sign(&signed_message(self, service));
+
+        self.insert(TXT_SIGNATURE_KEY, &STANDARD.encode(signature.to_bytes()))
+    }
+
+    fn signing_key_id(&self) -> Option<String> {
+        self.get(TXT_KEY_ID_KEY)
+    }
+
+    fn verify(&self, key: &VerifyingKey, service: &SignedService) -> Result<()> {
+        let encoded = self
+            .get(TXT_SIGNATURE_KEY)
+            .ok_or_else(|| invalid_signature("record is not signed"))?;
+
+        let bytes = STANDARD
+            .decode(encoded)
+            .map_err(|e| invalid_signature(&e.to_string()))?;
+
+        let signature =
+            Signature::from_slice(&bytes).map_err(|e| invalid_signature(&e.to_string()))?;
+
+        key.verify(&signed_message(self, service), &signature)
+            .map_err(|_| invalid_signature("signature does not match"))
+    }
+}
+
+impl ServiceDiscovery {
+    /// Verifies the signature of this service's TXT record against the specified key, for the
+    /// name, type, domain and port this service was discovered with.
+    ///
+    /// Returns `Error::InvalidTxtRecord` if the service has no TXT record or its signature does
+    /// not verify.
+    ///
+    /// See: [`TSignedTxtRecord::verify()`]
+    ///
+    /// [`TSignedTxtRecord::verify()`]: txt_signing/trait.TSignedTxtRecord.html#tymethod.verify
+    pub fn verify_txt(&self, key: &VerifyingKey) -> Result<()> {
+        self.txt()
+            .as_ref()
+            .ok_or_else(|| invalid_signature("service has no TXT record"))?
+            .verify(key, &SignedService::from(self))
+    }
+}
+
+/// Returns the canonical byte encoding of the fields covered by the signature: the name, type,
+/// domain and port of the service, followed by every entry but the signature, sorted by key.
+/// Each field is prefixed by its length.
+fn signed_message(txt: &TxtRecord, service: &SignedService) -> Vec<u8> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+
+    for (key, value) in txt.iter() {
+        if key != TXT_SIGNATURE_KEY && !entries.iter().any(|(k, _)| *k == key) {
+            entries.push((key, value));
+        }
+    }
+
+    entries.sort();
+
+    let service_type = service.service_type();
+    let kind = format!("_{}._{}", service_type.name(), service_type.protocol());
+    // the domain is reported with or without the trailing dot depending on the backend
+    let domain = service.domain().trim_end_matches('.').to_ascii_lowercase();
+
+    let mut message = Vec::new();
+
+    let mut push = |field: &str| {
+        message.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        message.extend_from_slice(field.as_bytes());
+    };
+
+    push(service.name());
+    push(&kind);
+    push(&domain);
+    push(&service.port().to_string());
+
+    for (key, value) in &entries {
+        push(key);
+        push(value);
+    }
+
+    message
+}
+
+fn invalid_signature(reason: &str) -> Error {
+    Error::InvalidTxtRecord(format!("signature: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn service() -> SignedService {
+        SignedService::builder()
+            .name("foo".into())
+            .service_type(ServiceType::new("http", "tcp").unwrap())
+            .domain("local".into())
+            .port(8080)
+            .build()
+            .unwrap()
+    }
+
+    fn signed_record() -> TxtRecord {
+        let mut record = TxtRecord::new();
+        record.insert("foo", "bar").unwrap();
+        record.insert("baz", "qux").unwrap();
+        record.sign("key-1", &signing_key(1), &service()).unwrap();
+        record
+    }
+
+    #[test]
+    fn verify_success() {
+        crate::tests::setup();
+
+        let record = signed_record();
+
+        assert_eq!(record.signing_key_id().unwrap(), "key-1");
+        assert_eq!(
+            record.verify(&signing_key(1).verifying_key(), &service()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn verify_ignores_entry_order() {
+        crate::tests::setup();
+
+        let record = signed_record().with_order(&["baz", TXT_SIGNATURE_KEY]);
+
+        assert_eq!(
+            record.verify(&signing_key(1).verifying_key(), &service()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn verify_rejects_wrong_key() {
+        crate::tests::setup();
+
+        signed_record()
+            .verify(&signing_key(2).verifying_key(), &service())
+            .expect_err("signature should not match");
+    }
+
+    #[test]
+    fn verify_rejects_modified_entry() {
+        crate::tests::setup();
+
+        let mut record = signed_record();
+        record.insert("foo", "spoofed").unwrap();
+
+        record
+            .verify(&signing_key(1).verifying_key(), &service())
+            .expect_err("signature should not match");
+    }
+
+    #[test]
+    fn verify_rejects_modified_key_id() {
+        crate::tests::setup();
+
+        let mut record = signed_record();
+        record.insert(TXT_KEY_ID_KEY, "key-2").unwrap();
+
+        record
+            .verify(&signing_key(1).verifying_key(), &service())
+            .expect_err("signature should not match");
+    }
+
+    #[test]
+    fn verify_rejects_other_service() {
+        crate::tests::setup();
+
+        let record = signed_record();
+        let key = signing_key(1).verifying_key();
+
+        let mut other = service();
+        other.name = "bar".into();
+        record
+            .verify(&key, &other)
+            .expect_err("name should not match");
+
+        let mut other = service();
+        other.port = 8081;
+        record
+            .verify(&key, &other)
+            .expect_err("port should not match");
+    }
+
+    #[test]
+    fn verify_ignores_domain_trailing_dot() {
+        crate::tests::setup();
+
+        let mut other = service();
+        other.domain = "local.".into();
+
+        assert_eq!(
+            signed_record().verify(&signing_key(1).verifying_key(), &other),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn verify_txt_uses_discovered_service() {
+        crate::tests::setup();
+
+        let service = service();
+
+        let discovery = ServiceDiscovery::builder()
+            .name(service.name().clone())
+            .service_type(service.service_type().clone())
+            .domain(service.domain().clone())
+            .host_name("foo.local".into())
+            .address("192.168.1.1".into())
+            .port(*service.port())
+            .txt(Some(signed_record()))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            discovery.verify_txt(&signing_key(1).verifying_key()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn verify_rejects_unsigned_record() {
+        crate::tests::setup();
+
+        let mut record = TxtRecord::new();
+        record.insert("foo", "bar").unwrap();
+
+        assert_eq!(record.signing_key_id(), None);
+        record
+            .verify(&signing_key(1).verifying_key(), &service())
+            .expect_err("record is not signed");
+    }
+
+    #[test]
+    fn sign_replaces_previous_signature() {
+        crate::tests::setup();
+
+        let mut record = signed_record();
+        record.sign("key-2", &signing_key(2), &service()).unwrap();
+
+        assert_eq!(record.signing_key_id().unwrap(), "key-2");
+        assert_eq!(
+            record.verify(&signing_key(2).verifying_key(), &service()),
+            Ok(())
+        );
+    }
+}